@@ -0,0 +1,67 @@
+use once_cell::sync::Lazy;
+use std::{collections::HashMap, sync::Mutex};
+
+/// Decompressed file contents, keyed by the address of the compressed data.
+///
+/// Embedded data lives for the entire program, so decompressing each file at
+/// most once and leaking the result is fine.
+static DECOMPRESSED: Lazy<Mutex<HashMap<usize, &'static [u8]>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// The algorithm used to compress a [`crate::File`]'s contents.
+///
+/// Compression is enabled by passing `compression = "..."` to
+/// [`crate::include_dir!()`], which requires the corresponding feature flag.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Compression {
+    /// Gzip (requires the `gzip` feature).
+    Gzip,
+    /// Zstandard (requires the `zstd` feature).
+    Zstd,
+    /// Brotli (requires the `brotli` feature).
+    Brotli,
+}
+
+impl Compression {
+    /// Get the decompressed version of some embedded data, decompressing it
+    /// the first time it is requested.
+    pub(crate) fn decompress_cached(self, data: &[u8]) -> &'static [u8] {
+        let key = data.as_ptr() as usize;
+
+        let mut cache = DECOMPRESSED.lock().unwrap();
+        cache
+            .entry(key)
+            .or_insert_with(|| Box::leak(self.decompress(data).into_boxed_slice()))
+    }
+
+    fn decompress(self, data: &[u8]) -> Vec<u8> {
+        match self {
+            #[cfg(feature = "gzip")]
+            Compression::Gzip => read_all(flate2::read::GzDecoder::new(data)),
+            #[cfg(feature = "zstd")]
+            Compression::Zstd => read_all(
+                zstd_crate::stream::read::Decoder::new(data)
+                    .expect("Unable to initialize the zstd decoder"),
+            ),
+            #[cfg(feature = "brotli")]
+            Compression::Brotli => read_all(brotli_crate::Decompressor::new(data, 4096)),
+            #[allow(unreachable_patterns)]
+            other => {
+                let _ = data;
+                panic!(
+                    "Decompressing {:?} data requires the corresponding feature flag",
+                    other
+                )
+            }
+        }
+    }
+}
+
+#[cfg(any(feature = "gzip", feature = "zstd", feature = "brotli"))]
+fn read_all(mut reader: impl std::io::Read) -> Vec<u8> {
+    let mut decompressed = Vec::new();
+    reader
+        .read_to_end(&mut decompressed)
+        .expect("Embedded data is always valid");
+    decompressed
+}
@@ -0,0 +1,48 @@
+use std::{ops::Deref, sync::Arc};
+
+/// A handle to a [`crate::File`]'s contents, as returned by
+/// [`crate::File::read()`].
+///
+/// Embedded contents are borrowed straight from the binary, while contents
+/// read from disk are reference counted and freed once every handle to a
+/// stale version is dropped.
+#[derive(Debug, Clone)]
+pub struct Contents<'a>(Inner<'a>);
+
+#[derive(Debug, Clone)]
+enum Inner<'a> {
+    Borrowed(&'a [u8]),
+    Shared(Arc<[u8]>),
+}
+
+impl<'a> Contents<'a> {
+    pub(crate) fn borrowed(contents: &'a [u8]) -> Self {
+        Contents(Inner::Borrowed(contents))
+    }
+
+    pub(crate) fn shared(contents: Arc<[u8]>) -> Self {
+        Contents(Inner::Shared(contents))
+    }
+
+    /// The contents as a byte slice.
+    pub fn as_slice(&self) -> &[u8] {
+        match &self.0 {
+            Inner::Borrowed(contents) => contents,
+            Inner::Shared(contents) => contents,
+        }
+    }
+}
+
+impl<'a> Deref for Contents<'a> {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        self.as_slice()
+    }
+}
+
+impl<'a> AsRef<[u8]> for Contents<'a> {
+    fn as_ref(&self) -> &[u8] {
+        self.as_slice()
+    }
+}
@@ -0,0 +1,320 @@
+//! Reading files and directories from disk at runtime, which is what debug
+//! builds do instead of embedding anything.
+
+use crate::{Dir, DirEntry, File};
+use once_cell::sync::Lazy;
+use std::{
+    collections::{HashMap, HashSet},
+    io,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, RwLock},
+    time::SystemTime,
+};
+
+/// In debug mode, the file is not read when compiling, it is read when it is
+/// used, and then placed in this cache.
+///
+/// Entries are keyed by the file's location on disk, so files with the same
+/// path in different embedded directories don't get mixed up. Most lookups
+/// only need a read lock, so threads reading files which haven't changed
+/// don't block each other.
+static FILES_CACHE: Lazy<RwLock<HashMap<PathBuf, Cached>>> =
+    Lazy::new(|| RwLock::new(HashMap::new()));
+
+struct Cached {
+    /// When the file was last modified at the time it was read, used to
+    /// notice when it has been edited.
+    modified: Option<SystemTime>,
+    contents: Arc<[u8]>,
+    /// A copy of `contents` that will never be freed, created the first time
+    /// someone needs a `&'static [u8]`.
+    leaked: Option<&'static [u8]>,
+}
+
+/// Get the contents of a file, reading it again if it was modified since the
+/// last time it was read.
+///
+/// Old versions are freed once the cache and every caller are done with
+/// them. Errors mention the path that couldn't be read.
+pub(crate) fn read_shared(path: &Path) -> io::Result<Arc<[u8]>> {
+    let modified = modified(path);
+
+    if let Some(cached) = FILES_CACHE.read().unwrap().get(path) {
+        if modified.is_some() && cached.modified == modified {
+            return Ok(Arc::clone(&cached.contents));
+        }
+    }
+
+    let contents: Arc<[u8]> = std::fs::read(path)
+        .map_err(|e| {
+            io::Error::new(
+                e.kind(),
+                format!("Unable to read \"{}\": {}", path.display(), e),
+            )
+        })?
+        .into();
+
+    let cached = Cached {
+        modified,
+        contents: Arc::clone(&contents),
+        leaked: None,
+    };
+    FILES_CACHE
+        .write()
+        .unwrap()
+        .insert(path.to_path_buf(), cached);
+
+    Ok(contents)
+}
+
+/// Like [`read_shared()`], except the contents are never freed so they can be
+/// handed out as a `&'static [u8]`.
+///
+/// Each version of a file is only leaked once, no matter how many times it
+/// is read.
+pub(crate) fn read(path: &Path) -> io::Result<&'static [u8]> {
+    let contents = read_shared(path)?;
+
+    if let Some(cached) = FILES_CACHE.read().unwrap().get(path) {
+        if let Some(leaked) = cached.leaked {
+            if Arc::ptr_eq(&cached.contents, &contents) {
+                return Ok(leaked);
+            }
+        }
+    }
+
+    let mut cache = FILES_CACHE.write().unwrap();
+
+    match cache.get_mut(path) {
+        Some(cached) if Arc::ptr_eq(&cached.contents, &contents) => {
+            // Another thread may have leaked it while we were waiting for
+            // the lock
+            Ok(*cached
+                .leaked
+                .get_or_insert_with(|| Box::leak(Box::new(contents))))
+        }
+        // The file changed again while we weren't holding the lock
+        _ => Ok(Box::leak(Box::new(contents))),
+    }
+}
+
+/// The entries in each directory that was listed, keyed by its location on
+/// disk and the part of that location which isn't included in the entries'
+/// paths.
+static LISTINGS: Lazy<RwLock<HashMap<(PathBuf, String), Listing>>> =
+    Lazy::new(|| RwLock::new(HashMap::new()));
+
+/// Every string that needed to outlive a [`Listing`].
+static STRINGS: Lazy<Mutex<HashSet<&'static str>>> = Lazy::new(|| Mutex::new(HashSet::new()));
+
+struct Listing {
+    modified: Option<SystemTime>,
+    entries: &'static [DirEntry<'static>],
+    /// Every version of the listing that was handed out.
+    ///
+    /// Entries are handed out as plain references, so old versions have to
+    /// be leaked in case they are still in use. Keeping them around means
+    /// each distinct version is only leaked once, so a directory whose
+    /// modification time keeps changing without anything being added or
+    /// removed (e.g. when an editor saves a file by replacing it) doesn't
+    /// leak more memory each time.
+    versions: Vec<&'static [DirEntry<'static>]>,
+}
+
+/// List the directory at `base/path` (relative to `root`), listing it again
+/// if its modification time changed because something was added or removed.
+///
+/// Nested directories are listed too, so their [`Dir::entries()`] are the
+/// same as they would be if the macro had generated them. They are listed
+/// again when their own entries are requested.
+pub(crate) fn list(
+    root: &str,
+    name: Option<&str>,
+    base: &str,
+    path: &str,
+) -> io::Result<&'static [DirEntry<'static>]> {
+    let dir = resolve_root(root, name).join(base).join(path);
+    let mut parents: Vec<_> = dir.canonicalize().into_iter().collect();
+
+    list_nested(root, name, base, path, &mut parents)
+}
+
+/// Like [`list()`], where `parents` are the canonical locations of the
+/// directories being listed further up, so symlinks which lead back to one
+/// of them aren't followed forever.
+fn list_nested(
+    root: &str,
+    name: Option<&str>,
+    base: &str,
+    path: &str,
+    parents: &mut Vec<PathBuf>,
+) -> io::Result<&'static [DirEntry<'static>]> {
+    let dir = resolve_root(root, name).join(base).join(path);
+    let modified = modified(&dir);
+    let key = (dir, base.to_string());
+
+    if let Some(listing) = LISTINGS.read().unwrap().get(&key) {
+        if modified.is_some() && listing.modified == modified {
+            return Ok(listing.entries);
+        }
+    }
+
+    let mut children = std::fs::read_dir(&key.0)
+        .and_then(|entries| entries.collect::<io::Result<Vec<_>>>())
+        .map_err(|e| {
+            io::Error::new(
+                e.kind(),
+                format!("Unable to list \"{}\": {}", key.0.display(), e),
+            )
+        })?;
+    // Lookups binary search the entries, so they need to be sorted the same
+    // way as the names they are given
+    children.sort_by_key(|child| child.file_name().to_string_lossy().into_owned());
+
+    let root = intern(root);
+    let base = intern(base);
+    let root_name = name.map(intern);
+    let mut entries = Vec::new();
+
+    for child in children {
+        let name = child.file_name();
+        let name = name.to_string_lossy();
+        let child_path = if path.is_empty() {
+            intern(&name)
+        } else {
+            intern(&format!("{}/{}", path, name))
+        };
+
+        // Anything that isn't a file or directory can't be read anyway
+        let full_path = child.path();
+        if full_path.is_dir() {
+            // A directory we are already inside is left empty, which is
+            // what listing it at runtime gives you if it can't be read
+            let nested = match full_path.canonicalize() {
+                Ok(location) if !parents.contains(&location) => {
+                    parents.push(location);
+                    let nested = list_nested(root, root_name, base, child_path, parents);
+                    parents.pop();
+                    nested.unwrap_or(&[])
+                }
+                _ => &[],
+            };
+
+            let dir = Dir::new(child_path, nested)
+                .with_root(root)
+                .with_path(child_path, base);
+            let dir = match root_name {
+                Some(name) => dir.with_root_name(name),
+                None => dir,
+            };
+            entries.push(DirEntry::Dir(dir));
+        } else if full_path.is_file() {
+            let file = File::new(child_path, &[]).with_root(root);
+            let file = match base {
+                "" => file,
+                base => file.with_source(intern(&format!("{}/{}", base, child_path))),
+            };
+            let file = match root_name {
+                Some(name) => file.with_root_name(name),
+                None => file,
+            };
+            entries.push(DirEntry::File(file));
+        }
+    }
+
+    let mut listings = LISTINGS.write().unwrap();
+    let listing = listings.entry(key).or_insert_with(|| Listing {
+        modified: None,
+        entries: &[],
+        versions: Vec::new(),
+    });
+
+    let entries = match listing
+        .versions
+        .iter()
+        .copied()
+        .find(|v| **v == entries[..])
+    {
+        Some(existing) => existing,
+        None => {
+            let leaked: &'static [DirEntry<'static>] = Box::leak(entries.into_boxed_slice());
+            listing.versions.push(leaked);
+            leaked
+        }
+    };
+    listing.modified = modified;
+    listing.entries = entries;
+
+    Ok(entries)
+}
+
+/// Named roots which were moved with [`Dir::set_root()`].
+static RELOCATED: Lazy<RwLock<HashMap<String, PathBuf>>> =
+    Lazy::new(|| RwLock::new(HashMap::new()));
+
+/// Use a different location for every file and directory with a particular
+/// root name.
+pub(crate) fn relocate(name: &str, path: PathBuf) {
+    RELOCATED.write().unwrap().insert(name.to_string(), path);
+}
+
+/// Work out where a root directory actually is, checking whether it was
+/// moved with [`Dir::set_root()`] or the `INCLUDE_DIR_ROOT_<NAME>`
+/// environment variable before using the path from compile time.
+pub(crate) fn resolve_root(root: &str, name: Option<&str>) -> PathBuf {
+    if let Some(name) = name {
+        if let Some(path) = RELOCATED.read().unwrap().get(name) {
+            return path.clone();
+        }
+
+        if let Some(path) = std::env::var_os(root_variable(name)) {
+            return PathBuf::from(path);
+        }
+    }
+
+    PathBuf::from(root)
+}
+
+/// The environment variable used to move a named root, e.g.
+/// `INCLUDE_DIR_ROOT_GAME_ASSETS` for `"game-assets"`.
+pub(crate) fn root_variable(name: &str) -> String {
+    let name: String = name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_uppercase()
+            } else {
+                '_'
+            }
+        })
+        .collect();
+
+    format!("INCLUDE_DIR_ROOT_{}", name)
+}
+
+/// Forget everything that has been read so far.
+pub(crate) fn clear() {
+    FILES_CACHE.write().unwrap().clear();
+    // Old listings are kept so their versions can be reused
+    for listing in LISTINGS.write().unwrap().values_mut() {
+        listing.modified = None;
+    }
+}
+
+/// Get a `'static` copy of a string, reusing an earlier copy if possible.
+pub(crate) fn intern(s: &str) -> &'static str {
+    let mut strings = STRINGS.lock().unwrap();
+
+    match strings.get(s) {
+        Some(existing) => existing,
+        None => {
+            let leaked: &'static str = Box::leak(s.to_string().into_boxed_str());
+            strings.insert(leaked);
+            leaked
+        }
+    }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    path.metadata().and_then(|m| m.modified()).ok()
+}
@@ -1,4 +1,6 @@
-use crate::{file::File, DirEntry};
+use crate::{file::File, overrides::Overrides, DirEntry, Hash};
+use std::cmp::Ordering;
+use std::ffi::OsStr;
 use std::fs;
 use std::path::Path;
 
@@ -7,12 +9,158 @@ use std::path::Path;
 pub struct Dir<'a> {
     path: &'a str,
     entries: &'a [DirEntry<'a>],
+    digest: Option<Hash>,
+    /// The directory to list entries from instead of using `entries`.
+    root: Option<&'a str>,
+    /// The part of the directory's location (relative to `root`) that isn't
+    /// included in `path`, for directories viewed with [`Dir::as_root()`].
+    base: &'a str,
+    /// A name which can be used to move the directory this [`Dir`] was
+    /// created from at runtime.
+    root_name: Option<&'a str>,
+    /// Whether `entries` are sorted by name, so they can be binary searched.
+    sorted: bool,
+    /// The positions of `entries` when sorted without caring about case.
+    case_index: Option<&'a [usize]>,
+    /// Files on disk which should be used instead of the embedded ones.
+    overrides: Option<Overrides>,
 }
 
 impl<'a> Dir<'a> {
     /// Create a new [`Dir`].
+    ///
+    /// The `entries` can be in any order. Use [`Dir::with_sorted()`] if they
+    /// are sorted by name, so lookups like [`Dir::get_entry()`] can use a
+    /// binary search.
     pub const fn new(path: &'a str, entries: &'a [DirEntry<'a>]) -> Self {
-        Dir { path, entries }
+        Dir {
+            path,
+            entries,
+            digest: None,
+            root: None,
+            base: "",
+            root_name: None,
+            sorted: false,
+            case_index: None,
+            overrides: None,
+        }
+    }
+
+    /// Set the [`Dir`]'s digest.
+    pub const fn with_digest(self, digest: Hash) -> Self {
+        Dir {
+            digest: Some(digest),
+            ..self
+        }
+    }
+
+    /// List the [`Dir`]'s entries by reading the disk whenever they are
+    /// requested instead of using the embedded entries, where the directory
+    /// is found by joining its path onto `root`.
+    ///
+    /// This is what [`crate::include_dir!()`] does in debug builds when
+    /// `live = true` is passed.
+    pub const fn with_root(self, root: &'a str) -> Self {
+        Dir {
+            root: Some(root),
+            ..self
+        }
+    }
+
+    /// Promise that the [`Dir`]'s entries are sorted by name, which lets
+    /// lookups like [`Dir::get_entry()`] use a binary search.
+    ///
+    /// This is what [`crate::include_dir!()`] does for every directory which
+    /// isn't listed at runtime. Lookups may miss entries if they aren't
+    /// actually sorted.
+    pub const fn with_sorted(self) -> Self {
+        Dir {
+            sorted: true,
+            ..self
+        }
+    }
+
+    /// Set the order of the [`Dir`]'s entries when their names are compared
+    /// without caring about case, which lets [`Dir::get_entry_ignore_case()`]
+    /// use a binary search.
+    ///
+    /// This is what [`crate::include_dir!()`] does for every directory which
+    /// isn't listed at runtime. An index which doesn't fit the entries is
+    /// ignored.
+    pub const fn with_case_index(self, index: &'a [usize]) -> Self {
+        Dir {
+            case_index: Some(index),
+            ..self
+        }
+    }
+
+    /// Give the [`Dir`] a name, so it can be moved at runtime using
+    /// [`Dir::set_root()`] or the `INCLUDE_DIR_ROOT_<NAME>` environment
+    /// variable.
+    ///
+    /// This is what [`crate::include_dir!()`] does when `name = "..."` is
+    /// passed.
+    pub const fn with_root_name(self, name: &'a str) -> Self {
+        Dir {
+            root_name: Some(name),
+            ..self
+        }
+    }
+
+    /// Read this [`Dir`]'s files from `path` instead of the directory they
+    /// were found in at compile time, for example because a debug build was
+    /// copied to another machine.
+    ///
+    /// This affects every file and directory embedded by the same
+    /// [`crate::include_dir!()`] call, and has no effect on files whose
+    /// contents were embedded.
+    ///
+    /// # Panics
+    ///
+    /// The [`Dir`] needs a [name][Dir::with_root_name], which is given by
+    /// passing `name = "..."` to [`crate::include_dir!()`].
+    pub fn set_root<P: AsRef<Path>>(&self, path: P) {
+        let name = self.root_name.unwrap_or_else(|| {
+            panic!(
+                "Unable to move \"{}\" because it doesn't have a name",
+                self.path
+            )
+        });
+
+        crate::dev::relocate(name, path.as_ref().to_path_buf());
+    }
+
+    /// The directory entries are listed from at runtime, if this is a
+    /// [live][Dir::with_root] directory.
+    #[cfg(feature = "watch")]
+    pub(crate) fn live_root(&self) -> Option<std::path::PathBuf> {
+        self.root
+            .map(|root| crate::dev::resolve_root(root, self.root_name).join(self.base))
+    }
+
+    /// Give the [`Dir`] a different path, where `base` is the part of its
+    /// location on disk that is no longer part of the path.
+    pub(crate) const fn with_path(self, path: &'a str, base: &'a str) -> Self {
+        Dir { path, base, ..self }
+    }
+
+    pub(crate) const fn with_entries(self, entries: &'a [DirEntry<'a>]) -> Self {
+        Dir { entries, ..self }
+    }
+
+    pub(crate) const fn base(&self) -> &'a str {
+        self.base
+    }
+
+    pub(crate) const fn overrides(&self) -> Option<Overrides> {
+        self.overrides
+    }
+
+    pub(crate) fn with_overrides(self, overrides: Overrides) -> Self {
+        Dir {
+            overrides: Some(overrides),
+            ..self
+        }
     }
 
     /// The full path for this [`Dir`], relative to the directory passed to
@@ -22,37 +170,224 @@ impl<'a> Dir<'a> {
     }
 
     /// The entries within this [`Dir`].
+    ///
+    /// These are the entries the [`Dir`] was created with. Directories with
+    /// a [root][Dir::with_root] are listed from disk instead, so use
+    /// [`Dir::current_entries()`] to see what they contain right now.
     pub const fn entries(&self) -> &'a [DirEntry<'a>] {
         self.entries
     }
 
+    /// The entries within this [`Dir`] right now.
+    ///
+    /// This is the same as [`Dir::entries()`], except directories with a
+    /// [root][Dir::with_root] are listed again whenever something is added
+    /// to or removed from them on disk, and have no entries if they can't be
+    /// read. Lookups like [`Dir::get_entry()`] and [`Dir::files()`] use this.
+    pub fn current_entries(&self) -> &'a [DirEntry<'a>] {
+        match self.root {
+            Some(root) => {
+                crate::dev::list(root, self.root_name, self.base, self.path).unwrap_or(&[])
+            }
+            None => self.entries,
+        }
+    }
+
+    /// A digest of everything inside this [`Dir`], calculated at compile time
+    /// from the hashes of its children.
+    ///
+    /// This is only available when `hash = "..."` was passed to
+    /// [`crate::include_dir!()`].
+    pub const fn digest(&self) -> Option<Hash> {
+        self.digest
+    }
+
     /// Get a list of the files in this directory.
     pub fn files(&self) -> impl Iterator<Item = &'a File<'a>> + 'a {
-        self.entries().iter().filter_map(DirEntry::as_file)
+        self.current_entries().iter().filter_map(DirEntry::as_file)
     }
 
     /// Get a list of the sub-directories inside this directory.
     pub fn dirs(&self) -> impl Iterator<Item = &'a Dir<'a>> + 'a {
-        self.entries().iter().filter_map(DirEntry::as_dir)
+        self.current_entries().iter().filter_map(DirEntry::as_dir)
     }
 
-    /// Recursively search for a [`DirEntry`] with a particular path.
+    /// Search for a [`DirEntry`] with a particular path.
+    ///
+    /// Paths are relative to the directory passed to
+    /// [`crate::include_dir!()`], even when looking inside a sub-directory
+    /// (use [`Dir::as_root()`] to look things up relative to a
+    /// sub-directory).
+    /// Each component of the path is found by binary searching the entries
+    /// at that level (or scanning them, if they aren't
+    /// [sorted][Dir::with_sorted]), so lookups only touch the directories
+    /// along the way.
+    ///
+    /// The path is normalized first, so paths taken from URLs or typed on
+    /// Windows can be passed straight in:
+    ///
+    /// - backslashes are treated like forward slashes
+    /// - leading slashes, empty components (`css//app.css`) and `.` are
+    ///   ignored
+    /// - `..` removes the component before it, and paths which would escape
+    ///   the root (e.g. `../secret.txt`) are never found
+    ///
+    /// ```rust
+    /// use include_dir::{include_dir, Dir};
+    ///
+    /// static SRC: Dir<'_> = include_dir!("$CARGO_MANIFEST_DIR/src");
+    ///
+    /// assert!(SRC.contains("/lib.rs"));
+    /// assert!(SRC.contains("./nested/../lib.rs"));
+    /// assert!(!SRC.contains("../src/lib.rs"));
+    /// ```
     pub fn get_entry<S: AsRef<Path>>(&self, path: S) -> Option<&'a DirEntry<'a>> {
-        let path = path.as_ref();
+        let path = normalize(path.as_ref())?;
+        let path = Path::new(&path);
+
+        if let Some(overrides) = &self.overrides {
+            return overrides.get_entry(path);
+        }
+
+        let relative = path.strip_prefix(self.path).ok()?;
+        let mut entries = self.current_entries();
+        let mut sorted = self.is_sorted();
+        let mut found = None;
 
-        for entry in self.entries() {
-            if entry.path() == path {
-                return Some(entry);
+        for component in relative.components() {
+            let entry = find(entries, sorted, component.as_os_str())?;
+
+            match entry {
+                DirEntry::Dir(d) => {
+                    entries = d.current_entries();
+                    sorted = d.is_sorted();
+                }
+                DirEntry::File(_) | DirEntry::Symlink(_) => entries = &[],
+            }
+            found = Some(entry);
+        }
+
+        found
+    }
+
+    /// Whether the entries we'd get from [`Dir::current_entries()`] are
+    /// sorted by name. Listings read at runtime always are.
+    pub(crate) const fn is_sorted(&self) -> bool {
+        self.sorted || self.root.is_some()
+    }
+
+    /// Get the directory containing the entry at `path`, or `None` if
+    /// there is no such entry.
+    ///
+    /// Entries directly inside this [`Dir`] have it as their parent.
+    ///
+    /// ```rust
+    /// use include_dir::{include_dir, Dir};
+    ///
+    /// static CRATE: Dir<'_> = include_dir!("$CARGO_MANIFEST_DIR");
+    ///
+    /// let lib_rs = CRATE.get_file("src/lib.rs").unwrap();
+    /// let src = CRATE.parent_of(lib_rs.path()).unwrap();
+    /// assert!(src.contains("src/dir.rs"));
+    /// ```
+    pub fn parent_of<S: AsRef<Path>>(&'a self, path: S) -> Option<&'a Dir<'a>> {
+        self.ancestors_of(path).next()
+    }
+
+    /// Get every directory containing the entry at `path`, starting with its
+    /// parent and ending with this [`Dir`].
+    ///
+    /// Nothing is returned if there is no such entry. The directories are
+    /// collected while walking down to the entry, with each one looked up in
+    /// the one before it, so this only searches one level at a time.
+    pub fn ancestors_of<S: AsRef<Path>>(
+        &'a self,
+        path: S,
+    ) -> impl Iterator<Item = &'a Dir<'a>> + 'a {
+        let mut ancestors = vec![self];
+
+        let found = normalize(path.as_ref()).and_then(|path| {
+            let depth = self.path.split('/').filter(|c| !c.is_empty()).count();
+            let mut current = self;
+
+            // Every '/' after this directory's own path ends an ancestor
+            for (end, _) in path.match_indices('/').skip(depth) {
+                current = current.get_dir(&path[..end])?;
+                ancestors.push(current);
             }
 
-            if let DirEntry::Dir(d) = entry {
-                if let Some(nested) = d.get_entry(path) {
-                    return Some(nested);
+            current.get_entry(&path)
+        });
+
+        if found.is_none() {
+            ancestors.clear();
+        }
+
+        ancestors.into_iter().rev()
+    }
+
+    /// Like [`Dir::get_entry()`], except upper and lower case letters are
+    /// treated as the same, so `"img/Logo.PNG"` finds `img/logo.png`.
+    ///
+    /// If several entries only differ by case, the one that sorts first is
+    /// used ([`crate::include_dir!()`] warns about these when compiling).
+    /// Files from an [override root][Dir::with_override_root] are only found
+    /// when their case matches an embedded file, or the path exactly.
+    pub fn get_entry_ignore_case<S: AsRef<Path>>(&self, path: S) -> Option<&'a DirEntry<'a>> {
+        let path = normalize(path.as_ref())?;
+
+        if self.overrides.is_some() {
+            let embedded = Dir {
+                overrides: None,
+                ..self.clone()
+            };
+
+            return match embedded.get_entry_ignore_case(&path) {
+                Some(entry) => self.get_entry(entry.path()),
+                None => self.get_entry(&path),
+            };
+        }
+
+        let mut components = path.split('/').filter(|c| !c.is_empty());
+
+        for expected in self.path.split('/').filter(|c| !c.is_empty()) {
+            if cmp_ignore_case(components.next()?, expected) != Ordering::Equal {
+                return None;
+            }
+        }
+
+        let mut entries = self.current_entries();
+        let mut index = self.case_index();
+        let mut found = None;
+
+        for component in components {
+            let entry = find_ignoring_case(entries, index, component)?;
+
+            match entry {
+                DirEntry::Dir(d) => {
+                    entries = d.current_entries();
+                    index = d.case_index();
+                }
+                DirEntry::File(_) | DirEntry::Symlink(_) => {
+                    entries = &[];
+                    index = None;
                 }
             }
+            found = Some(entry);
         }
 
-        None
+        found
+    }
+
+    /// The case index, if it can be used with the entries we'd get from
+    /// [`Dir::current_entries()`].
+    fn case_index(&self) -> Option<&'a [usize]> {
+        match self.root {
+            Some(_) => None,
+            None => self
+                .case_index
+                .filter(|index| index.len() == self.entries.len()),
+        }
     }
 
     /// Look up a file by name.
@@ -77,7 +412,7 @@ impl<'a> Dir<'a> {
     pub fn extract<S: AsRef<Path>>(&self, base_path: S) -> std::io::Result<()> {
         let base_path = base_path.as_ref();
 
-        for entry in self.entries() {
+        for entry in self.current_entries() {
             let path = base_path.join(entry.path());
 
             match entry {
@@ -88,9 +423,178 @@ impl<'a> Dir<'a> {
                 DirEntry::File(f) => {
                     fs::write(path, f.contents())?;
                 }
+                DirEntry::Symlink(s) => {
+                    create_symlink(s.target(), &path)?;
+                }
             }
         }
 
         Ok(())
     }
 }
+
+impl Dir<'static> {
+    /// Get a copy of this [`Dir`] where looking up a file by path checks
+    /// `root` first, using the file on disk instead of the embedded one if it
+    /// exists.
+    ///
+    /// This lets people replace assets in a release build without
+    /// recompiling. Files found this way are read from disk (and read again
+    /// when they change), and can be looked up even if nothing with the same
+    /// path was embedded. Overrides only apply to lookups like
+    /// [`Dir::get_file()`] and [`Dir::contains()`], not [`Dir::current_entries()`].
+    ///
+    /// ```rust
+    /// use include_dir::{include_dir, Dir};
+    ///
+    /// static ASSETS: Dir<'_> = include_dir!("$CARGO_MANIFEST_DIR/src");
+    ///
+    /// let assets = ASSETS.with_override_root("mods");
+    /// let lib_rs = assets.get_file("lib.rs").unwrap();
+    /// ```
+    pub fn with_override_root<P: AsRef<Path>>(&self, root: P) -> Dir<'static> {
+        let overrides = Overrides::new(root.as_ref(), self.path, self.entries, self.sorted);
+        self.clone().with_overrides(overrides)
+    }
+}
+
+/// Find the entry called `name`, using a binary search when the entries are
+/// sorted.
+fn find<'a>(entries: &'a [DirEntry<'a>], sorted: bool, name: &OsStr) -> Option<&'a DirEntry<'a>> {
+    let name = Some(name);
+
+    if sorted {
+        let index = entries
+            .binary_search_by(|entry| entry.path().file_name().cmp(&name))
+            .ok()?;
+        Some(&entries[index])
+    } else {
+        entries
+            .iter()
+            .find(|entry| entry.path().file_name() == name)
+    }
+}
+
+/// Find the first entry called `name` without caring about case, using a
+/// binary search when there is an index.
+///
+/// Indices which point past the end of `entries` weren't generated by
+/// [`crate::include_dir!()`], so the entries are scanned instead.
+fn find_ignoring_case<'a>(
+    entries: &'a [DirEntry<'a>],
+    index: Option<&[usize]>,
+    name: &str,
+) -> Option<&'a DirEntry<'a>> {
+    if let Some(index) = index {
+        let mut valid = true;
+        let position = index.partition_point(|&i| match entries.get(i) {
+            Some(entry) => cmp_ignore_case(file_name(entry), name).is_lt(),
+            None => {
+                valid = false;
+                false
+            }
+        });
+
+        if valid {
+            let entry = entries.get(*index.get(position)?)?;
+
+            return if cmp_ignore_case(file_name(entry), name) == Ordering::Equal {
+                Some(entry)
+            } else {
+                None
+            };
+        }
+    }
+
+    entries
+        .iter()
+        .find(|entry| cmp_ignore_case(file_name(entry), name) == Ordering::Equal)
+}
+
+fn file_name<'a>(entry: &DirEntry<'a>) -> &'a str {
+    entry
+        .path()
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or_default()
+}
+
+/// Compare two names as if they were both lower case, the same way
+/// [`crate::include_dir!()`] does when generating the case index.
+fn cmp_ignore_case(a: &str, b: &str) -> Ordering {
+    a.chars()
+        .flat_map(char::to_lowercase)
+        .cmp(b.chars().flat_map(char::to_lowercase))
+}
+
+/// Normalize a path so it can be compared with the paths generated by
+/// [`crate::include_dir!()`], returning `None` if it escapes the root.
+fn normalize(path: &Path) -> Option<String> {
+    let path = path.to_string_lossy();
+    let mut components = Vec::new();
+
+    for component in path.split(|c| c == '/' || c == '\\') {
+        match component {
+            "" | "." => {}
+            ".." => {
+                components.pop()?;
+            }
+            other => components.push(other),
+        }
+    }
+
+    Some(components.join("/"))
+}
+
+impl Dir<'static> {
+    /// Get a view of this [`Dir`] which acts as if it had been passed to
+    /// [`crate::include_dir!()`] itself.
+    ///
+    /// Everything inside the view has a path relative to this directory, and
+    /// lookups use relative paths too, so a sub-directory can be handed to
+    /// code which doesn't know where it came from.
+    ///
+    /// ```rust
+    /// use include_dir::{include_dir, Dir};
+    ///
+    /// static CRATE: Dir<'_> = include_dir!("$CARGO_MANIFEST_DIR");
+    ///
+    /// let src = CRATE.get_dir("src").unwrap().as_root();
+    /// let lib_rs = src.get_file("lib.rs").unwrap();
+    /// assert_eq!(lib_rs.path(), std::path::Path::new("lib.rs"));
+    /// ```
+    ///
+    /// Views are only created once for each directory, so calling this
+    /// repeatedly is cheap.
+    pub fn as_root(&'static self) -> &'static Dir<'static> {
+        crate::view::as_root(self)
+    }
+}
+
+#[cfg(unix)]
+fn create_symlink(target: &Path, link: &Path) -> std::io::Result<()> {
+    std::os::unix::fs::symlink(target, link)
+}
+
+#[cfg(windows)]
+fn create_symlink(target: &Path, link: &Path) -> std::io::Result<()> {
+    // Windows needs to know whether the link points to a directory
+    let resolved = link.parent().map(|parent| parent.join(target));
+
+    if resolved.map_or(false, |p| p.is_dir()) {
+        std::os::windows::fs::symlink_dir(target, link)
+    } else {
+        std::os::windows::fs::symlink_file(target, link)
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
+fn create_symlink(_target: &Path, link: &Path) -> std::io::Result<()> {
+    Err(std::io::Error::new(
+        std::io::ErrorKind::Other,
+        format!(
+            "Unable to create \"{}\" because symlinks aren't supported on this platform",
+            link.display()
+        ),
+    ))
+}
@@ -1,4 +1,4 @@
-use crate::{Dir, File};
+use crate::{Dir, File, Symlink};
 use std::path::Path;
 
 /// A directory entry, roughly analogous to [`std::fs::DirEntry`].
@@ -8,6 +8,8 @@ pub enum DirEntry<'a> {
     Dir(Dir<'a>),
     /// A file.
     File(File<'a>),
+    /// A symbolic link.
+    Symlink(Symlink<'a>),
 }
 
 impl<'a> DirEntry<'a> {
@@ -16,6 +18,7 @@ impl<'a> DirEntry<'a> {
         match self {
             DirEntry::Dir(d) => d.path(),
             DirEntry::File(f) => f.path(),
+            DirEntry::Symlink(s) => s.path(),
         }
     }
 
@@ -23,7 +26,7 @@ impl<'a> DirEntry<'a> {
     pub fn as_dir(&self) -> Option<&Dir<'a>> {
         match self {
             DirEntry::Dir(d) => Some(d),
-            DirEntry::File(_) => None,
+            DirEntry::File(_) | DirEntry::Symlink(_) => None,
         }
     }
 
@@ -31,15 +34,23 @@ impl<'a> DirEntry<'a> {
     pub fn as_file(&self) -> Option<&File<'a>> {
         match self {
             DirEntry::File(f) => Some(f),
-            DirEntry::Dir(_) => None,
+            DirEntry::Dir(_) | DirEntry::Symlink(_) => None,
+        }
+    }
+
+    /// Try to get this as a [`Symlink`], if it is one.
+    pub fn as_symlink(&self) -> Option<&Symlink<'a>> {
+        match self {
+            DirEntry::Symlink(s) => Some(s),
+            DirEntry::Dir(_) | DirEntry::File(_) => None,
         }
     }
 
     /// Get this item's sub-items, if it has any.
     pub fn children(&self) -> &'a [DirEntry<'a>] {
         match self {
-            DirEntry::Dir(d) => d.entries(),
-            DirEntry::File(_) => &[],
+            DirEntry::Dir(d) => d.current_entries(),
+            DirEntry::File(_) | DirEntry::Symlink(_) => &[],
         }
     }
 }
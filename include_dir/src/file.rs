@@ -1,46 +1,102 @@
+use crate::{Compression, Contents, Hash};
 use std::{
     fmt::{self, Debug, Formatter},
-    path::Path,
+    io,
+    path::{Path, PathBuf},
 };
 
-#[cfg(debug_assertions)]
-use std::{sync::Mutex, collections::HashMap};
-#[cfg(debug_assertions)]
-use once_cell::sync::Lazy;
-
-/// In debug mode, the file is not read when compiling, it is read when it is used, and then placed in this cache.
-#[cfg(debug_assertions)]
-static FILES_CACHE: Lazy<Mutex<HashMap<&'static str, &'static [u8]>>> = Lazy::new(|| {
-    Mutex::new(HashMap::new())
-});
-
 /// A file with its contents stored in a `&'static [u8]`.
 #[derive(Clone, PartialEq, Eq)]
 pub struct File<'a> {
     path: &'a str,
     contents: &'a [u8],
+    compression: Option<Compression>,
+    hash: Option<Hash>,
     #[cfg(feature = "metadata")]
     metadata: Option<crate::Metadata>,
-    #[cfg(debug_assertions)]
-    prefix: &'a str,
+    #[cfg(feature = "mime")]
+    mime_type: Option<&'a str>,
+    #[cfg(feature = "mime")]
+    is_text: Option<bool>,
+    /// The directory to read the file from instead of using `contents`.
+    root: Option<&'a str>,
+    /// A name which can be used to move `root` at runtime.
+    root_name: Option<&'a str>,
+    /// Where the file can be found on disk, relative to `root`.
+    source: &'a str,
 }
 
 impl<'a> File<'a> {
     /// Create a new [`File`].
-    pub const fn new(path: &'a str, contents: &'a [u8],
-                     #[cfg(debug_assertions)]
-                     prefix: &'a str,
-    ) -> Self {
+    pub const fn new(path: &'a str, contents: &'a [u8]) -> Self {
         File {
             path,
             contents,
+            compression: None,
+            hash: None,
             #[cfg(feature = "metadata")]
             metadata: None,
-            #[cfg(debug_assertions)]
-            prefix,
+            #[cfg(feature = "mime")]
+            mime_type: None,
+            #[cfg(feature = "mime")]
+            is_text: None,
+            root: None,
+            root_name: None,
+            source: path,
+        }
+    }
+
+    /// Mark the [`File`]'s contents as being compressed with a particular
+    /// algorithm.
+    pub const fn with_compression(self, compression: Compression) -> Self {
+        File {
+            compression: Some(compression),
+            ..self
+        }
+    }
+
+    /// Set the [`Hash`][struct@Hash] of the [`File`]'s contents.
+    pub const fn with_hash(self, hash: Hash) -> Self {
+        File {
+            hash: Some(hash),
+            ..self
+        }
+    }
+
+    /// Read the [`File`]'s contents from disk instead of using the embedded
+    /// data, where the file is found by joining its path onto `root`.
+    ///
+    /// This is what [`crate::include_dir!()`] does in debug builds.
+    pub const fn with_root(self, root: &'a str) -> Self {
+        File {
+            root: Some(root),
+            ..self
         }
     }
 
+    /// Give the [`File`]'s [root][File::with_root] a name, so it can be moved
+    /// at runtime using [`Dir::set_root()`][crate::Dir::set_root] or the
+    /// `INCLUDE_DIR_ROOT_<NAME>` environment variable.
+    pub const fn with_root_name(self, name: &'a str) -> Self {
+        File {
+            root_name: Some(name),
+            ..self
+        }
+    }
+
+    /// Read the [`File`]'s contents from a different location (relative to
+    /// the root passed to [`File::with_root()`]), for files that were moved or
+    /// renamed by [`crate::include_dir!()`].
+    pub const fn with_source(self, source: &'a str) -> Self {
+        File { source, ..self }
+    }
+
+    /// Give the [`File`] a different path without changing where it is read
+    /// from.
+    pub(crate) const fn with_path(self, path: &'a str) -> Self {
+        File { path, ..self }
+    }
+
     /// The full path for this [`File`], relative to the directory passed to
     /// [`crate::include_dir!()`].
     pub fn path(&self) -> &'a Path {
@@ -48,22 +104,99 @@ impl<'a> File<'a> {
     }
 
     /// The file's raw contents.
+    ///
+    /// Compressed files are decompressed the first time this is called.
+    /// Files with a [root][File::with_root] are read from disk instead, and
+    /// read again whenever their modification time changes.
+    ///
+    /// # Panics
+    ///
+    /// This panics if the file needs to be read from disk and that fails (for
+    /// example, because it was deleted). Use [`File::try_contents()`] to
+    /// handle the error instead.
+    ///
+    /// Because the returned slice may be used for as long as the [`File`]
+    /// exists, each version of a file read from disk is kept in memory for
+    /// the rest of the program. Use [`File::read()`] to avoid this.
     pub fn contents(&self) -> &[u8] {
-        #[cfg(debug_assertions)]
-        {
-            let mut cache = FILES_CACHE.lock().unwrap();
-            if !cache.contains_key(self.path) {
-                let real_path = self.prefix.to_string().clone() + std::path::MAIN_SEPARATOR.to_string().as_str() + self.path;
-                let real_path : &Path = Path::new(real_path.as_str());
-                let value = Box::leak(std::fs::read(real_path).unwrap().into_boxed_slice());
-                let key = Box::leak(self.path.to_string().into_boxed_str());
-                cache.insert(key, value);
-            }
-            cache.get(self.path).unwrap()
+        self.try_contents().unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// The file's raw contents, returning an error if they needed to be read
+    /// from disk and couldn't be.
+    ///
+    /// Embedded files never fail.
+    pub fn try_contents(&self) -> io::Result<&[u8]> {
+        if let Some(root) = self.root {
+            return crate::dev::read(&self.disk_path(root));
+        }
+
+        match self.compression {
+            Some(compression) => Ok(compression.decompress_cached(self.contents)),
+            None => Ok(self.contents),
         }
-        #[cfg(not(debug_assertions))]
-        {
-            self.contents
+    }
+
+    /// Get a handle to the file's contents.
+    ///
+    /// This is the same as [`File::try_contents()`] for embedded files, but
+    /// files read from disk (e.g. in debug builds) are reference counted
+    /// instead of being kept around for the rest of the program. Prefer this
+    /// in long-running programs which pick up changes to files, so old
+    /// versions can be freed.
+    pub fn read(&self) -> io::Result<Contents<'a>> {
+        if let Some(root) = self.root {
+            let contents = crate::dev::read_shared(&self.disk_path(root))?;
+            return Ok(Contents::shared(contents));
+        }
+
+        match self.compression {
+            Some(compression) => Ok(Contents::borrowed(
+                compression.decompress_cached(self.contents),
+            )),
+            None => Ok(Contents::borrowed(self.contents)),
+        }
+    }
+
+    /// The directory this file is read from at runtime and the file's
+    /// location inside it, if its contents weren't embedded.
+    #[cfg(feature = "watch")]
+    pub(crate) fn location(&self) -> Option<(PathBuf, &'a str)> {
+        self.root
+            .map(|root| (crate::dev::resolve_root(root, self.root_name), self.source))
+    }
+
+    fn disk_path(&self, root: &str) -> PathBuf {
+        crate::dev::resolve_root(root, self.root_name).join(self.source)
+    }
+
+    /// The file's contents as they are stored in the binary, if they were
+    /// compressed.
+    pub fn compressed_contents(&self) -> Option<(Compression, &'a [u8])> {
+        self.compression
+            .map(|compression| (compression, self.contents))
+    }
+
+    /// The hash of the file's contents, calculated at compile time.
+    ///
+    /// This is only available when `hash = "..."` was passed to
+    /// [`crate::include_dir!()`]. It is only guaranteed to match the
+    /// contents when they were embedded, because files read from disk may
+    /// have changed since the program was compiled.
+    pub const fn hash(&self) -> Option<Hash> {
+        self.hash
+    }
+
+    /// Check whether two [`File`]s refer to the same storage in the binary.
+    ///
+    /// [`crate::include_dir!()`] only embeds each unique blob of contents
+    /// once, so this is `true` for any two files with identical contents.
+    /// Files that are read from disk (e.g. in debug builds) only share
+    /// storage with files read from the same place.
+    pub fn shares_contents_with(&self, other: &File<'_>) -> bool {
+        match (self.root, other.root) {
+            (None, None) => std::ptr::eq(self.contents, other.contents),
+            (ours, theirs) => ours == theirs && self.source == other.source,
         }
     }
 
@@ -71,23 +204,28 @@ impl<'a> File<'a> {
     pub fn contents_utf8(&self) -> Option<&str> {
         std::str::from_utf8(self.contents()).ok()
     }
+
+    /// The file's contents interpreted as a string, returning an error if
+    /// they couldn't be read or aren't valid UTF-8.
+    pub fn try_contents_utf8(&self) -> io::Result<&str> {
+        let contents = self.try_contents()?;
+
+        std::str::from_utf8(contents).map_err(|e| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("\"{}\" is not valid UTF-8: {}", self.path, e),
+            )
+        })
+    }
 }
 
 #[cfg(feature = "metadata")]
 impl<'a> File<'a> {
     /// Set the [`Metadata`] associated with a [`File`].
     pub const fn with_metadata(self, metadata: crate::Metadata) -> Self {
-        #[cfg(not(debug_assertions))]
-        let File { path, contents , .. } = self;
-        #[cfg(debug_assertions)]
-        let File { path, contents,prefix , .. } = self;
-
         File {
-            path,
-            contents,
             metadata: Some(metadata),
-            #[cfg(debug_assertions)]
-            prefix,
+            ..self
         }
     }
 
@@ -97,27 +235,74 @@ impl<'a> File<'a> {
     }
 }
 
+#[cfg(feature = "mime")]
+impl<'a> File<'a> {
+    /// Set the [`File`]'s MIME type and whether it contains text.
+    pub const fn with_mime_type(self, mime_type: &'a str, is_text: bool) -> Self {
+        File {
+            mime_type: Some(mime_type),
+            is_text: Some(is_text),
+            ..self
+        }
+    }
+
+    /// The [`File`]'s MIME type (e.g. `"text/html"`), if known.
+    ///
+    /// [`crate::include_dir!()`] guesses this from the file's extension when
+    /// it is embedded, falling back to looking at the first few bytes for
+    /// common formats. Files that still can't be identified are either
+    /// `"text/plain"` or `"application/octet-stream"`. Like [`File::hash()`],
+    /// this describes the file as it was when compiling.
+    pub fn mime_type(&self) -> Option<&'a str> {
+        self.mime_type
+    }
+
+    /// Does the [`File`] contain text (valid UTF-8 without any null bytes)?
+    pub fn is_text(&self) -> bool {
+        match self.is_text {
+            Some(is_text) => is_text,
+            None => {
+                let contents = self.contents();
+                !contents.contains(&0) && std::str::from_utf8(contents).is_ok()
+            }
+        }
+    }
+}
+
 impl<'a> Debug for File<'a> {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         let File {
             path,
             contents,
+            compression,
+            hash,
             #[cfg(feature = "metadata")]
             metadata,
-            #[cfg(debug_assertions)]
-            prefix,
+            #[cfg(feature = "mime")]
+            mime_type,
+            #[cfg(feature = "mime")]
+            is_text,
+            root,
+            root_name,
+            source,
         } = self;
 
         let mut d = f.debug_struct("File");
 
         d.field("path", path)
-            .field("contents", &format!("<{} bytes>", contents.len()));
+            .field("contents", &format!("<{} bytes>", contents.len()))
+            .field("compression", compression)
+            .field("hash", hash);
 
         #[cfg(feature = "metadata")]
         d.field("metadata", metadata);
 
-        #[cfg(debug_assertions)]
-        d.field("prefix", prefix);
+        #[cfg(feature = "mime")]
+        d.field("mime_type", mime_type).field("is_text", is_text);
+
+        d.field("root", root)
+            .field("root_name", root_name)
+            .field("source", source);
 
         d.finish()
     }
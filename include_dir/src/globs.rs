@@ -18,7 +18,7 @@ struct Globs<'a> {
 
 impl<'a> Globs<'a> {
     pub(crate) fn new(pattern: Pattern, root: &Dir<'a>) -> Globs<'a> {
-        let stack = root.entries().iter().collect();
+        let stack = root.current_entries().iter().collect();
         Globs { stack, pattern }
     }
 }
@@ -0,0 +1,57 @@
+use std::fmt::{self, Display, Formatter};
+
+/// The algorithm used to calculate a [`Hash`][struct@Hash].
+///
+/// Hashes are calculated at compile time by passing `hash = "..."` to
+/// [`crate::include_dir!()`], which requires the corresponding feature flag.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum HashAlgorithm {
+    /// SHA-256 (requires the `sha256` feature).
+    Sha256,
+    /// BLAKE3 (requires the `blake3` feature).
+    Blake3,
+}
+
+/// A 256-bit hash which was calculated when the data was embedded.
+///
+/// For a [`crate::File`] this is the hash of its (uncompressed) contents,
+/// while a [`crate::Dir`]'s digest combines the names and hashes of
+/// everything inside it, so it changes whenever any entry is added, removed,
+/// renamed or modified.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Hash {
+    algorithm: HashAlgorithm,
+    bytes: [u8; 32],
+}
+
+impl Hash {
+    /// Create a new [`Hash`][struct@Hash].
+    pub const fn new(algorithm: HashAlgorithm, bytes: [u8; 32]) -> Self {
+        Hash { algorithm, bytes }
+    }
+
+    /// The algorithm this hash was calculated with.
+    pub const fn algorithm(&self) -> HashAlgorithm {
+        self.algorithm
+    }
+
+    /// The raw bytes.
+    pub const fn as_bytes(&self) -> &[u8; 32] {
+        &self.bytes
+    }
+
+    /// The hash as a lowercase hexadecimal string.
+    pub fn to_hex(&self) -> String {
+        self.to_string()
+    }
+}
+
+impl Display for Hash {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for byte in &self.bytes {
+            write!(f, "{:02x}", byte)?;
+        }
+
+        Ok(())
+    }
+}
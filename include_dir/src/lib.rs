@@ -43,6 +43,175 @@
 //! }
 //! ```
 //!
+//! # Merging Directories
+//!
+//! The macro accepts more than one path, in which case the directories are
+//! merged into a single tree. When the same path exists in several
+//! directories, the one from the directory listed last wins, so you can put
+//! your defaults first and overrides afterwards.
+//!
+//! ```rust
+//! use include_dir::{include_dir, Dir};
+//!
+//! static ASSETS: Dir<'_> = include_dir!(
+//!     "$CARGO_MANIFEST_DIR/src",
+//!     "$CARGO_MANIFEST_DIR/tests",
+//! );
+//!
+//! assert!(ASSETS.contains("lib.rs"));
+//! assert!(ASSETS.contains("integration_test.rs"));
+//! ```
+//!
+//! Use `on_conflict = "error"` to fail the build instead of silently replacing
+//! entries.
+//!
+//! # Options
+//!
+//! The paths may be followed by a list of `key = value` options which control
+//! what gets embedded. Filters are applied to each directory separately.
+//!
+//! - `include` - a list of glob patterns. When present, only files matching
+//!   at least one pattern are embedded
+//! - `exclude` - a list of glob patterns for files and directories which
+//!   should be skipped. This takes priority over `include`
+//! - `max_depth` - how many levels of directories to descend into, where `1`
+//!   only embeds the root's immediate children
+//! - `gitignore` - when `true`, skip anything ignored by `.gitignore` or
+//!   `.ignore` files (including those in parent directories) and the global
+//!   git excludes (requires the `gitignore` feature). The `.git` directory
+//!   is always skipped in this mode
+//! - `skip_hidden` - when `true`, skip files and directories whose name starts
+//!   with a `.`
+//! - `flatten` - a list of glob patterns for directories whose contents should
+//!   be moved up into their parent directory
+//! - `rename_extensions` - a list of `"from" => "to"` pairs for changing file
+//!   extensions (e.g. `"min.js" => "js"`). Use an empty string to remove the
+//!   extension altogether. Only the first matching rule is applied
+//! - `mount` - a directory (e.g. `"static"`) that everything should be placed
+//!   under, as if the embedded directory was a sub-directory of it
+//! - `symlinks` - what to do when a symlink is found. This can be `"follow"`
+//!   (the default) to embed whatever it points to, `"skip"` to leave it out,
+//!   `"error"` to fail the build, or `"preserve"` to embed the link itself as
+//!   a [`DirEntry::Symlink`]. Following a symlink which leads to one of its
+//!   own parent directories is always an error
+//! - `special_files` - what to do with anything that can't be embedded, like
+//!   FIFOs, sockets and broken symlinks. Either `"error"` (the default) or
+//!   `"skip"`
+//! - `compression` - compress file contents with `"gzip"`, `"zstd"` or
+//!   `"brotli"` (each requires the feature flag with the same name). Files
+//!   are decompressed the first time [`File::contents()`] is called, and the
+//!   raw bytes are available via [`File::compressed_contents()`]. Files which
+//!   don't get smaller are left uncompressed
+//! - `hash` - calculate a `"sha256"` or `"blake3"` hash of each file's
+//!   contents (requires the feature flag with the same name), available via
+//!   [`File::hash()`]. Each directory also gets a [`Dir::digest()`] which
+//!   combines the names and hashes of everything inside it. Hashes describe
+//!   the files as they were when compiling, so they won't match files read
+//!   from disk which were edited while the program was running
+//! - `mode` - `"embed"` to always embed file contents, `"disk"` to always
+//!   read them from disk at runtime, or `"auto"` (the default) to read from
+//!   disk in debug builds and embed in release builds. See
+//!   [*Debug Builds*](#debug-builds)
+//! - `live` - when `true`, debug builds list directories from disk whenever
+//!   [`Dir::current_entries()`] (or anything that uses it) is called, so
+//!   files added after compiling are visible too. This can't be combined
+//!   with several directories, filters (`include`, `exclude`, `max_depth`,
+//!   `gitignore`, `skip_hidden` and `symlinks`) or with `flatten`,
+//!   `rename_extensions` or `mount`
+//! - `name` - a name for the directory so files read from disk at runtime
+//!   can be found somewhere else. See [*Debug Builds*](#debug-builds)
+//! - `max_file_size` - the largest file (in bytes) that may be embedded
+//! - `max_total_size` - the most data (in bytes) that may be embedded in
+//!   total, where files with identical contents are only counted once
+//! - `on_conflict` - either `"override"` (the default) or `"error"`, see
+//!   [*Merging Directories*](#merging-directories). This also applies when
+//!   `flatten` or `rename_extensions` would give two entries the same path
+//!
+//! These rewrites are applied in the order listed, after the directories are
+//! filtered and merged, and are reflected in [`Dir::path()`] and
+//! [`File::path()`].
+//!
+//! ```rust
+//! use include_dir::{include_dir, Dir};
+//! use std::path::Path;
+//!
+//! static WEBSITE: Dir<'_> = include_dir!(
+//!     "$CARGO_MANIFEST_DIR/src",
+//!     rename_extensions = ["rs" => "txt"],
+//!     mount = "static",
+//! );
+//!
+//! let lib_rs = WEBSITE.get_file("static/lib.txt").unwrap();
+//! assert_eq!(lib_rs.path(), Path::new("static/lib.txt"));
+//! ```
+//!
+//! Glob patterns are matched against the path relative to the root, with `*`
+//! never matching a `/`. Directories which end up empty because all of their
+//! contents were filtered out are left out entirely.
+//!
+//! ```rust
+//! use include_dir::{include_dir, Dir};
+//!
+//! static SOURCE_CODE: Dir<'_> = include_dir!(
+//!     "$CARGO_MANIFEST_DIR",
+//!     include = ["**/*.rs"],
+//!     exclude = ["tests/**"],
+//! );
+//!
+//! assert!(SOURCE_CODE.contains("src/lib.rs"));
+//! assert!(!SOURCE_CODE.contains("Cargo.toml"));
+//! assert!(!SOURCE_CODE.contains("tests"));
+//!
+//! // roughly the files that would be committed to version control
+//! # #[cfg(feature = "gitignore")]
+//! static TRACKED: Dir<'_> = include_dir!(
+//!     "$CARGO_MANIFEST_DIR",
+//!     gitignore = true,
+//!     skip_hidden = true,
+//! );
+//! ```
+//!
+//! # Debug Builds
+//!
+//! To keep compile times down, debug builds don't embed any file contents.
+//! Instead, each file is read from its original location the first time
+//! [`File::contents()`] is called. Files are read again whenever their
+//! modification time changes, so edits show up without restarting the
+//! program, and [`reload()`] can be used to throw away everything that was
+//! read so far.
+//!
+//! Every version of a file handed out by [`File::contents()`] stays in memory
+//! until the program exits. Long-running programs that pick up lots of edits
+//! can use [`File::read()`] instead, which returns a reference-counted
+//! handle so stale versions are freed once nobody is using them.
+//!
+//! The directory structure is still fixed at compile time unless you pass
+//! `live = true`.
+//!
+//! Files are looked for at the absolute path they had when compiling, which
+//! breaks as soon as the binary is run on another machine or the project is
+//! moved. Giving the directory a `name` lets you point it somewhere else,
+//! either by calling [`Dir::set_root()`] or by setting the
+//! `INCLUDE_DIR_ROOT_<NAME>` environment variable at runtime, where `<NAME>`
+//! is the name in upper case with anything other than letters and digits
+//! replaced by `_` (e.g. `INCLUDE_DIR_ROOT_GAME_ASSETS` for
+//! `name = "game-assets"`).
+//!
+//! This can be changed for a single [`include_dir!()`] call with the `mode`
+//! option, or for every call by setting the `INCLUDE_DIR_MODE` environment
+//! variable to `embed`, `disk` or `auto` when compiling. Enabling the `embed`
+//! feature overrides both, guaranteeing that the resulting binary is
+//! self-contained no matter how it was built.
+//!
+//! Release builds can still pick up files from disk with
+//! [`Dir::with_override_root()`], which lets people drop replacement assets
+//! into a directory next to your program without recompiling it.
+//!
+//! Programs which need to know *when* something changed (e.g. to rebuild
+//! derived state like compiled shaders) can enable the `watch` feature and
+//! use `Dir::watch()` to be told about files being created, modified or
+//! removed. This does nothing when file contents are embedded.
+//!
 //! # Features
 //!
 //! This library exposes a couple feature flags for enabling and disabling extra
@@ -52,6 +221,17 @@
 //! - `metadata` - include some basic filesystem metadata like last modified
 //!   time. This is not enabled by default to allow for more reproducible builds
 //!   and to hide potentially identifying information.
+//! - `gzip`, `zstd`, `brotli` - allow embedded files to be compressed using
+//!   the corresponding algorithm
+//! - `sha256`, `blake3` - allow hashes of embedded files to be calculated at
+//!   compile time
+//! - `gitignore` - allow skipping files ignored by `.gitignore` with the
+//!   `gitignore` option
+//! - `embed` - always embed file contents, even in debug builds
+//! - `mime` - work out each file's MIME type and whether it contains text
+//!   when it is embedded (see `File::mime_type()` and `File::is_text()`)
+//! - `watch` - get notified when files read from disk at runtime are
+//!   created, modified or removed (see `Dir::watch()`)
 //! - `nightly` - enables nightly APIs like [`track_path`][track-path]
 //!   and  [`proc_macro_tracked_env`][tracked-env]. This gives the compiler
 //!   more information about what is accessed by the procedural macro, enabling
@@ -76,6 +256,26 @@
 //! Using `include_dir!("target/")` increased the compile time to 5 seconds
 //! and used 730MB of RAM, generating a 72MB binary.
 //!
+//! To make sure this doesn't happen by accident, you can set an upper limit
+//! on the size of embedded files with the `max_file_size` and
+//! `max_total_size` options. Compilation will fail with a list of the
+//! offending files if either limit is exceeded.
+//!
+//! ```rust
+//! use include_dir::{include_dir, Dir};
+//!
+//! static ASSETS: Dir<'_> = include_dir!(
+//!     "$CARGO_MANIFEST_DIR/src",
+//!     max_file_size = 1_000_000,
+//!     max_total_size = 10_000_000,
+//! );
+//! ```
+//!
+//! Files with identical contents are only embedded once, so duplicated
+//! assets (e.g. a `LICENSE` copied into each locale's directory) don't add to
+//! the size of your binary. You can use [`File::shares_contents_with()`] to
+//! check whether two files point at the same data.
+//!
 //! [tracked-env]: https://github.com/rust-lang/rust/issues/74690
 //! [track-path]: https://github.com/rust-lang/rust/issues/73921
 //! [cargo-vars]: https://doc.rust-lang.org/cargo/reference/environment-variables.html#environment-variables-cargo-sets-for-crates
@@ -90,9 +290,16 @@
 )]
 #![cfg_attr(feature = "nightly", feature(doc_cfg))]
 
+mod compression;
+mod contents;
+mod dev;
 mod dir;
 mod dir_entry;
 mod file;
+mod hash;
+mod overrides;
+mod symlink;
+mod view;
 
 #[cfg(feature = "metadata")]
 mod metadata;
@@ -100,12 +307,37 @@ mod metadata;
 #[cfg(feature = "glob")]
 mod globs;
 
+#[cfg(feature = "watch")]
+mod watch;
+
 #[cfg(feature = "metadata")]
 pub use crate::metadata::Metadata;
 
-pub use crate::{dir::Dir, dir_entry::DirEntry, file::File};
+#[cfg(feature = "watch")]
+pub use crate::watch::{Change, Watcher};
+
+pub use crate::{
+    compression::Compression,
+    contents::Contents,
+    dir::Dir,
+    dir_entry::DirEntry,
+    file::File,
+    hash::{Hash, HashAlgorithm},
+    symlink::Symlink,
+};
 pub use include_dir_macros::include_dir;
 
+/// Forget the contents of every file that has been read from disk, so the
+/// next call to [`File::contents()`] reads it again.
+///
+/// Files are already read again when their modification time changes, so
+/// this is only needed when that isn't reliable (e.g. on some network
+/// filesystems). It has no effect on files whose contents are embedded in the
+/// binary.
+pub fn reload() {
+    dev::clear();
+}
+
 #[doc = include_str!("../README.md")]
 #[allow(dead_code)]
 fn check_readme_examples() {}
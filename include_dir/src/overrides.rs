@@ -0,0 +1,102 @@
+//! Letting files on disk replace embedded ones at runtime.
+
+use crate::{dev::intern, Dir, DirEntry, File};
+use once_cell::sync::Lazy;
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::RwLock,
+};
+
+/// Entries created while looking things up in an override directory, keyed
+/// by their location on disk, the address of the embedded entries they were
+/// looked up in and whether they are directories.
+///
+/// Lookups hand out plain references, so each entry is created once and
+/// kept for the rest of the program. Several embedded directories can share
+/// an override root, so the embedded entries are part of the key.
+static ENTRIES: Lazy<RwLock<HashMap<EntryKey, &'static DirEntry<'static>>>> =
+    Lazy::new(|| RwLock::new(HashMap::new()));
+
+type EntryKey = (PathBuf, usize, bool);
+
+/// A directory whose files should be used instead of the embedded ones.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub(crate) struct Overrides {
+    root: &'static str,
+    /// The path of the directory the embedded entries are in.
+    path: &'static str,
+    /// The embedded entries to fall back to.
+    entries: &'static [DirEntry<'static>],
+    /// Whether `entries` are sorted by name.
+    sorted: bool,
+}
+
+impl Overrides {
+    pub(crate) fn new(
+        root: &Path,
+        path: &'static str,
+        entries: &'static [DirEntry<'static>],
+        sorted: bool,
+    ) -> Self {
+        Overrides {
+            root: intern(&root.to_string_lossy()),
+            path,
+            entries,
+            sorted,
+        }
+    }
+
+    /// The same overrides for a sub-directory viewed with
+    /// [`Dir::as_root()`], where `entries` are its embedded entries.
+    pub(crate) fn join(&self, path: &str, entries: &'static [DirEntry<'static>]) -> Self {
+        Overrides::new(&Path::new(self.root).join(path), "", entries, self.sorted)
+    }
+
+    /// Look up an entry, preferring a file in the override directory over
+    /// the embedded entry with the same path.
+    pub(crate) fn get_entry(&self, path: &Path) -> Option<&'static DirEntry<'static>> {
+        let on_disk = Path::new(self.root).join(path);
+        let embedded = self.entries.as_ptr() as usize;
+
+        if on_disk.is_file() {
+            return Some(cached((on_disk, embedded, false), || {
+                let path = intern(&path.to_string_lossy().replace('\\', "/"));
+                DirEntry::File(File::new(path, &[]).with_root(self.root))
+            }));
+        }
+
+        let mut fallback = Dir::new(self.path, self.entries);
+        if self.sorted {
+            fallback = fallback.with_sorted();
+        }
+
+        match fallback.get_entry(path)? {
+            // Lookups inside sub-directories should check for overrides too
+            DirEntry::Dir(d) => Some(cached((on_disk, embedded, true), || {
+                let overrides = Overrides {
+                    root: self.root,
+                    // Paths are always created from a `&str`
+                    path: d.path().to_str().unwrap_or_default(),
+                    entries: d.current_entries(),
+                    sorted: d.is_sorted(),
+                };
+                DirEntry::Dir(d.clone().with_overrides(overrides))
+            })),
+            embedded => Some(embedded),
+        }
+    }
+}
+
+fn cached(key: EntryKey, create: impl FnOnce() -> DirEntry<'static>) -> &'static DirEntry<'static> {
+    if let Some(entry) = ENTRIES.read().unwrap().get(&key) {
+        return entry;
+    }
+
+    let mut entries = ENTRIES.write().unwrap();
+    let entry = entries
+        .entry(key)
+        .or_insert_with(|| Box::leak(Box::new(create())));
+
+    entry
+}
@@ -0,0 +1,33 @@
+use std::path::Path;
+
+/// A symbolic link, embedded as-is when `symlinks = "preserve"` is passed to
+/// [`crate::include_dir!()`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Symlink<'a> {
+    path: &'a str,
+    target: &'a str,
+}
+
+impl<'a> Symlink<'a> {
+    /// Create a new [`Symlink`].
+    pub const fn new(path: &'a str, target: &'a str) -> Self {
+        Symlink { path, target }
+    }
+
+    pub(crate) const fn with_path(self, path: &'a str) -> Self {
+        Symlink { path, ..self }
+    }
+
+    /// The full path for this [`Symlink`], relative to the directory passed to
+    /// [`crate::include_dir!()`].
+    pub fn path(&self) -> &'a Path {
+        Path::new(self.path)
+    }
+
+    /// Where the link points, exactly as it was written on disk.
+    ///
+    /// Relative targets are relative to the directory containing the link.
+    pub fn target(&self) -> &'a Path {
+        Path::new(self.target)
+    }
+}
@@ -0,0 +1,84 @@
+//! Treating a sub-directory as if it was passed to `include_dir!()` itself.
+
+use crate::{dev::intern, Dir, DirEntry};
+use once_cell::sync::Lazy;
+use std::{collections::HashMap, path::Path, sync::RwLock};
+
+/// Every view that was created, keyed by the address of the directory it was
+/// created from.
+///
+/// Views hand out plain references, so each one is created once and kept for
+/// the rest of the program. Directories with a `'static` address are never
+/// freed, so their addresses can't be reused by something else.
+static VIEWS: Lazy<RwLock<HashMap<usize, &'static Dir<'static>>>> =
+    Lazy::new(|| RwLock::new(HashMap::new()));
+
+/// Get a copy of `dir` where every path is relative to `dir` instead of the
+/// original root.
+pub(crate) fn as_root(dir: &'static Dir<'static>) -> &'static Dir<'static> {
+    let key = dir as *const Dir<'static> as usize;
+
+    if let Some(view) = VIEWS.read().unwrap().get(&key) {
+        return view;
+    }
+
+    let prefix = path_str(dir.path());
+    let base = match (dir.base(), prefix) {
+        (base, "") => base,
+        ("", prefix) => prefix,
+        (base, prefix) => intern(&format!("{}/{}", base, prefix)),
+    };
+
+    let view = rebase(dir, prefix, base).with_path("", base);
+    let view = match dir.overrides() {
+        Some(overrides) => {
+            let overrides = overrides.join(prefix, view.entries());
+            view.with_overrides(overrides)
+        }
+        None => view,
+    };
+
+    let mut views = VIEWS.write().unwrap();
+    views
+        .entry(key)
+        .or_insert_with(|| Box::leak(Box::new(view)))
+}
+
+/// Copy a directory, removing `prefix` from the start of every path inside
+/// it.
+fn rebase(dir: &Dir<'static>, prefix: &str, base: &'static str) -> Dir<'static> {
+    let entries: Vec<_> = dir
+        .entries()
+        .iter()
+        .map(|entry| match entry {
+            DirEntry::Dir(d) => {
+                let path = strip(path_str(d.path()), prefix);
+                DirEntry::Dir(rebase(d, prefix, base).with_path(path, base))
+            }
+            DirEntry::File(f) => {
+                DirEntry::File(f.clone().with_path(strip(path_str(f.path()), prefix)))
+            }
+            DirEntry::Symlink(s) => {
+                DirEntry::Symlink(s.with_path(strip(path_str(s.path()), prefix)))
+            }
+        })
+        .collect();
+
+    dir.clone()
+        .with_entries(Box::leak(entries.into_boxed_slice()))
+}
+
+fn path_str(path: &'static Path) -> &'static str {
+    // Paths are always created from a `&str`
+    path.to_str().unwrap_or_default()
+}
+
+fn strip<'a>(path: &'a str, prefix: &str) -> &'a str {
+    if prefix.is_empty() {
+        return path;
+    }
+
+    path.strip_prefix(prefix)
+        .and_then(|rest| rest.strip_prefix('/'))
+        .unwrap_or(path)
+}
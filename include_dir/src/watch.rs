@@ -0,0 +1,278 @@
+//! Finding out when files read from disk at runtime change.
+
+use crate::{Dir, DirEntry};
+use notify::{
+    event::{ModifyKind, RenameMode},
+    Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher as _,
+};
+use std::{
+    collections::{BTreeSet, HashMap, HashSet},
+    io,
+    path::{Component, Path, PathBuf},
+    sync::mpsc::{self, Receiver},
+};
+
+/// Something that happened to a file or directory inside a watched [`Dir`].
+///
+/// Paths are relative to the directory passed to [`crate::include_dir!()`],
+/// just like [`DirEntry::path()`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Change {
+    /// A file or directory was created (or moved into place).
+    Created(PathBuf),
+    /// A file or directory was modified.
+    Modified(PathBuf),
+    /// A file or directory was removed (or moved away).
+    Removed(PathBuf),
+}
+
+impl Change {
+    /// The path of the file or directory that changed.
+    pub fn path(&self) -> &Path {
+        match self {
+            Change::Created(path) | Change::Modified(path) | Change::Removed(path) => path,
+        }
+    }
+}
+
+/// A handle returned by [`Dir::watch()`]. Changes are reported until it is
+/// dropped.
+#[derive(Debug)]
+#[must_use = "Changes stop being reported when the Watcher is dropped"]
+pub struct Watcher {
+    _inner: Option<RecommendedWatcher>,
+}
+
+impl<'a> Dir<'a> {
+    /// Call `callback` whenever something inside this [`Dir`] is created,
+    /// modified or removed on disk.
+    ///
+    /// Only files which are read from disk at runtime (e.g. in debug builds)
+    /// are watched, so this does nothing when file contents were embedded.
+    /// The callback is called from a background thread.
+    ///
+    /// ```rust
+    /// use include_dir::{include_dir, Dir};
+    ///
+    /// static ASSETS: Dir<'_> = include_dir!("$CARGO_MANIFEST_DIR/src");
+    ///
+    /// let _watcher = ASSETS
+    ///     .watch(|change| println!("{} changed", change.path().display()))
+    ///     .unwrap();
+    /// ```
+    pub fn watch<F>(&self, mut callback: F) -> io::Result<Watcher>
+    where
+        F: FnMut(Change) + Send + 'static,
+    {
+        let mut locations = Locations::default();
+        locations.add(self);
+
+        if locations.roots.is_empty() {
+            return Ok(Watcher { _inner: None });
+        }
+
+        let watched = locations.watched();
+        let prefix = PathBuf::from(self.path());
+        let handler = move |event: notify::Result<Event>| {
+            if let Ok(event) = event {
+                for change in locations.changes(event) {
+                    if change.path().starts_with(&prefix) {
+                        callback(change);
+                    }
+                }
+            }
+        };
+
+        let mut watcher = notify::recommended_watcher(handler).map_err(|e| {
+            io::Error::new(
+                io::ErrorKind::Other,
+                format!("Unable to watch for changes: {}", e),
+            )
+        })?;
+
+        for (path, mode) in &watched {
+            watcher.watch(path, *mode).map_err(|e| {
+                io::Error::new(
+                    io::ErrorKind::Other,
+                    format!("Unable to watch \"{}\": {}", path.display(), e),
+                )
+            })?;
+        }
+
+        Ok(Watcher {
+            _inner: Some(watcher),
+        })
+    }
+
+    /// Like [`Dir::watch()`], except changes are sent to a channel.
+    ///
+    /// When nothing needs to be watched the channel is disconnected straight
+    /// away, because no changes will ever be sent.
+    pub fn watch_channel(&self) -> io::Result<(Watcher, Receiver<Change>)> {
+        let (sender, receiver) = mpsc::channel();
+        let watcher = self.watch(move |change| {
+            let _ = sender.send(change);
+        })?;
+
+        Ok((watcher, receiver))
+    }
+}
+
+/// Where on disk everything inside a [`Dir`] comes from.
+#[derive(Debug, Default)]
+struct Locations {
+    roots: BTreeSet<PathBuf>,
+    /// Directories whose entries are listed at runtime, so anything created
+    /// inside them is part of the [`Dir`].
+    live: BTreeSet<PathBuf>,
+    /// Every file that was included.
+    files: HashSet<PathBuf>,
+    /// The path each file was given, for files which were moved or renamed.
+    paths: HashMap<PathBuf, PathBuf>,
+}
+
+impl Locations {
+    fn add(&mut self, dir: &Dir<'_>) {
+        if let Some(root) = dir.live_root() {
+            self.live.insert(root.join(dir.path()));
+            self.roots.insert(root);
+        }
+
+        for entry in dir.current_entries() {
+            match entry {
+                DirEntry::Dir(d) => self.add(d),
+                DirEntry::File(f) => {
+                    if let Some((root, source)) = f.location() {
+                        self.add_file(root, source, f.path());
+                    }
+                }
+                DirEntry::Symlink(_) => {}
+            }
+        }
+    }
+
+    fn add_file(&mut self, root: PathBuf, source: &str, path: &Path) {
+        let path_str = path.to_str().unwrap_or_default();
+        self.files.insert(root.join(source));
+
+        // Files viewed with Dir::as_root() are inside a sub-directory of the
+        // root, and their path is relative to that
+        match source.strip_suffix(path_str) {
+            Some(base) if base.is_empty() || base.ends_with('/') => {
+                self.roots.insert(root.join(base));
+            }
+            _ => {
+                self.paths.insert(root.join(source), path.to_path_buf());
+                self.roots.insert(root);
+            }
+        }
+    }
+
+    /// Where to listen for changes.
+    ///
+    /// Live directories are watched recursively because anything created
+    /// inside them shows up in the [`Dir`]. Everything else only needs the
+    /// directories containing the included files, which are watched on their
+    /// own so the rest of the root (e.g. `target/`) doesn't use up watches.
+    /// Watching the directory instead of the file means files replaced by
+    /// renaming something over them are still noticed.
+    fn watched(&self) -> Vec<(PathBuf, RecursiveMode)> {
+        let in_live_dir = |path: &Path| {
+            self.live
+                .iter()
+                .any(|dir| dir.as_path() != path && path.starts_with(dir))
+        };
+
+        let live = self
+            .live
+            .iter()
+            .filter(|dir| !in_live_dir(dir))
+            .map(|dir| (dir.clone(), RecursiveMode::Recursive));
+
+        let parents: BTreeSet<_> = self
+            .files
+            .iter()
+            .filter_map(|file| file.parent())
+            .filter(|parent| !self.live.contains(*parent) && !in_live_dir(parent))
+            .collect();
+        let parents = parents
+            .into_iter()
+            .map(|parent| (parent.to_path_buf(), RecursiveMode::NonRecursive));
+
+        live.chain(parents).collect()
+    }
+
+    fn changes(&self, event: Event) -> Vec<Change> {
+        let change: fn(PathBuf) -> Change = match event.kind {
+            EventKind::Create(_) | EventKind::Modify(ModifyKind::Name(RenameMode::To)) => {
+                Change::Created
+            }
+            EventKind::Remove(_) | EventKind::Modify(ModifyKind::Name(RenameMode::From)) => {
+                Change::Removed
+            }
+            // The first path is where it came from, the second is where it went
+            EventKind::Modify(ModifyKind::Name(RenameMode::Both)) => {
+                return event
+                    .paths
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(i, path)| {
+                        let path = self.relative(path)?;
+                        Some(match i {
+                            0 => Change::Removed(path),
+                            _ => Change::Created(path),
+                        })
+                    })
+                    .collect();
+            }
+            EventKind::Modify(_) => Change::Modified,
+            EventKind::Access(_) | EventKind::Any | EventKind::Other => return Vec::new(),
+        };
+
+        event
+            .paths
+            .iter()
+            .filter_map(|path| self.relative(path))
+            .map(change)
+            .collect()
+    }
+
+    /// Turn a location on disk into the path it would have inside the
+    /// [`Dir`], or `None` if it isn't part of the [`Dir`].
+    ///
+    /// Roots are watched recursively, so this also gets called for things
+    /// the macro left out (e.g. `target/` when the whole crate is included).
+    fn relative(&self, path: &Path) -> Option<PathBuf> {
+        if let Some(renamed) = self.paths.get(path) {
+            return Some(renamed.clone());
+        }
+
+        let included =
+            self.files.contains(path) || self.live.iter().any(|dir| path.starts_with(dir));
+        if !included {
+            return None;
+        }
+
+        // Roots are sorted, so nested roots come after the roots they're in
+        let relative = self
+            .roots
+            .iter()
+            .rev()
+            .find_map(|root| path.strip_prefix(root).ok())?;
+
+        // Embedded paths always use forward slashes
+        let components: Vec<_> = relative
+            .components()
+            .filter_map(|c| match c {
+                Component::Normal(name) => Some(name.to_string_lossy()),
+                _ => None,
+            })
+            .collect();
+
+        if components.is_empty() {
+            None
+        } else {
+            Some(PathBuf::from(components.join("/")))
+        }
+    }
+}
@@ -1,8 +1,65 @@
-use include_dir::{include_dir, Dir};
+use include_dir::{include_dir, Dir, File};
 use std::path::Path;
 use tempfile::TempDir;
 
 static PARENT_DIR: Dir<'_> = include_dir!("$CARGO_MANIFEST_DIR");
+static RUST_FILES: Dir<'_> = include_dir!(
+    "$CARGO_MANIFEST_DIR",
+    include = ["**/*.rs"],
+    exclude = ["src/glob*.rs"]
+);
+static SHALLOW_DIR: Dir<'_> = include_dir!("$CARGO_MANIFEST_DIR", max_depth = 1);
+#[cfg(feature = "gitignore")]
+static NOT_IGNORED: Dir<'_> = include_dir!(
+    "$CARGO_MANIFEST_DIR/tests/fixtures/ignore",
+    gitignore = true,
+    skip_hidden = true
+);
+static MERGED: Dir<'_> = include_dir!(
+    "$CARGO_MANIFEST_DIR/tests/fixtures/defaults",
+    "$CARGO_MANIFEST_DIR/tests/fixtures/product"
+);
+static DEFAULTS: Dir<'_> = include_dir!("$CARGO_MANIFEST_DIR/tests/fixtures/defaults");
+static PRODUCT: Dir<'_> = include_dir!("$CARGO_MANIFEST_DIR/tests/fixtures/product");
+static REWRITTEN: Dir<'_> = include_dir!(
+    "$CARGO_MANIFEST_DIR/tests/fixtures/web",
+    flatten = ["dist"],
+    rename_extensions = ["min.js" => "js"],
+    mount = "static"
+);
+static SYMLINKS: Dir<'_> = include_dir!(
+    "$CARGO_MANIFEST_DIR",
+    include = ["README.md", "src/lib.rs"],
+    symlinks = "preserve"
+);
+static DUPLICATES: Dir<'_> = include_dir!("$CARGO_MANIFEST_DIR/tests/fixtures/duplicates");
+static EMBEDDED: Dir<'_> = include_dir!(
+    "$CARGO_MANIFEST_DIR/tests/fixtures/duplicates",
+    mode = "embed"
+);
+static ON_DISK: Dir<'_> = include_dir!(
+    "$CARGO_MANIFEST_DIR/tests/fixtures/duplicates",
+    mode = "disk"
+);
+static RELOCATABLE: Dir<'_> = include_dir!(
+    "$CARGO_MANIFEST_DIR/tests/fixtures/duplicates",
+    name = "relocatable",
+    mode = "disk"
+);
+static FROM_ENV: Dir<'_> = include_dir!(
+    "$CARGO_MANIFEST_DIR/tests/fixtures/duplicates",
+    name = "from-env",
+    mode = "disk",
+    live = true
+);
+static LIVE: Dir<'_> = include_dir!("$CARGO_MANIFEST_DIR/tests/fixtures/duplicates", live = true);
+#[cfg(feature = "sha256")]
+static HASHED: Dir<'_> = include_dir!(
+    "$CARGO_MANIFEST_DIR/tests/fixtures/duplicates",
+    hash = "sha256"
+);
+#[cfg(feature = "gzip")]
+static GZIPPED: Dir<'_> = include_dir!("$CARGO_MANIFEST_DIR/src", compression = "gzip");
 
 #[test]
 fn included_all_files_in_the_include_dir_crate() {
@@ -12,6 +69,634 @@ fn included_all_files_in_the_include_dir_crate() {
     assert!(PARENT_DIR.contains("src/lib.rs"));
 }
 
+#[test]
+fn filter_entries_with_globs() {
+    assert!(RUST_FILES.contains("src/lib.rs"));
+    assert!(RUST_FILES.contains("tests/integration_test.rs"));
+    assert!(!RUST_FILES.contains("src/globs.rs"));
+    assert!(!RUST_FILES.contains("Cargo.toml"));
+    assert!(all_files(&RUST_FILES)
+        .iter()
+        .all(|f| f.path().extension().unwrap() == "rs"));
+}
+
+#[test]
+fn limit_the_depth() {
+    assert!(SHALLOW_DIR.contains("Cargo.toml"));
+    assert!(SHALLOW_DIR.get_dir("src").unwrap().entries().is_empty());
+    assert!(!SHALLOW_DIR.contains("src/lib.rs"));
+}
+
+#[test]
+#[cfg(feature = "gitignore")]
+fn skip_ignored_and_hidden_files() {
+    let paths: Vec<_> = NOT_IGNORED.entries().iter().map(|e| e.path()).collect();
+
+    assert_eq!(paths, [Path::new("keep.txt")]);
+}
+
+#[test]
+fn merge_several_directories() {
+    let logo = MERGED.get_file("logo.txt").unwrap();
+    assert_eq!(logo.contents_utf8().unwrap(), "product logo\n");
+
+    assert!(MERGED.contains("style.css"));
+    assert!(MERGED.contains("img/a.txt"));
+    assert!(MERGED.contains("img/b.txt"));
+    assert_eq!(MERGED.get_dir("img").unwrap().entries().len(), 2);
+}
+
+#[test]
+fn look_up_entries_by_walking_the_tree() {
+    let fr = DUPLICATES.get_dir("fr").unwrap();
+
+    assert_eq!(
+        fr.get_file("fr/greeting.txt").unwrap().path(),
+        Path::new("fr/greeting.txt")
+    );
+    assert!(DUPLICATES.get_file("fr/greeting.txt").is_some());
+    assert!(DUPLICATES.get_dir("fr/greeting.txt").is_none());
+    assert!(!DUPLICATES.contains("fr/greeting.txt/nested"));
+    assert!(!DUPLICATES.contains("fr/missing.txt"));
+    assert!(!fr.contains("en/LICENSE"));
+    assert!(!fr.contains("fr"));
+}
+
+#[test]
+fn look_up_entries_in_hand_built_directories() {
+    use include_dir::DirEntry;
+
+    static NESTED: [DirEntry<'_>; 2] = [
+        DirEntry::File(File::new("b/z.txt", b"z")),
+        DirEntry::File(File::new("b/a.txt", b"a")),
+    ];
+    static ENTRIES: [DirEntry<'_>; 2] = [
+        DirEntry::File(File::new("c.txt", b"c")),
+        DirEntry::Dir(Dir::new("b", &NESTED)),
+    ];
+    static UNSORTED: Dir<'_> = Dir::new("", &ENTRIES);
+
+    for path in ["c.txt", "b/z.txt", "b/a.txt"] {
+        assert!(UNSORTED.contains(path), "{}", path);
+    }
+    assert!(UNSORTED.contains("b"));
+    assert!(!UNSORTED.contains("b/missing.txt"));
+}
+
+#[test]
+fn normalize_paths_before_looking_them_up() {
+    let greeting = DUPLICATES.get_file("fr/greeting.txt").unwrap();
+
+    for path in [
+        "./fr/greeting.txt",
+        "/fr/greeting.txt",
+        "fr//greeting.txt",
+        "fr\\greeting.txt",
+        "en/../fr/./greeting.txt",
+    ] {
+        assert_eq!(DUPLICATES.get_file(path), Some(greeting), "{}", path);
+    }
+
+    assert!(!DUPLICATES.contains("../duplicates/fr/greeting.txt"));
+    assert!(!DUPLICATES.contains("fr/../../duplicates/fr/greeting.txt"));
+    assert!(!DUPLICATES.contains("/"));
+}
+
+#[test]
+fn look_up_entries_without_caring_about_case() {
+    let greeting = DUPLICATES.get_file("fr/greeting.txt").unwrap();
+    let fr = DUPLICATES.get_dir("fr").unwrap();
+    let assets = EMBEDDED.with_override_root("missing");
+
+    for dir in [&DUPLICATES, fr, &LIVE, &assets] {
+        let entry = dir.get_entry_ignore_case("FR/Greeting.TXT").unwrap();
+        assert_eq!(entry.path(), greeting.path());
+    }
+
+    assert!(DUPLICATES.get_entry("FR/Greeting.TXT").is_none());
+    assert!(DUPLICATES
+        .get_entry_ignore_case("fr/greeting.txt/x")
+        .is_none());
+    assert!(DUPLICATES
+        .get_entry_ignore_case("../FR/greeting.txt")
+        .is_none());
+    assert!(fr.get_entry_ignore_case("EN/license").is_none());
+}
+
+#[test]
+fn ignore_case_indices_which_dont_fit_the_entries() {
+    use include_dir::DirEntry;
+
+    static ENTRIES: [DirEntry<'_>; 1] = [DirEntry::File(File::new("a.txt", b"a"))];
+    static DIR: Dir<'_> = Dir::new("", &ENTRIES).with_case_index(&[5]);
+
+    assert!(DIR.get_entry_ignore_case("A.TXT").is_some());
+    assert!(DIR.get_entry_ignore_case("b.txt").is_none());
+}
+
+#[test]
+fn view_a_sub_directory_as_its_own_root() {
+    let fr = DUPLICATES.get_dir("fr").unwrap().as_root();
+
+    assert_eq!(fr.path(), Path::new(""));
+    let greeting = fr.get_file("greeting.txt").unwrap();
+    assert_eq!(greeting.path(), Path::new("greeting.txt"));
+    assert_eq!(greeting.contents(), b"Bonjour\n");
+    assert!(!fr.contains("fr/greeting.txt"));
+    assert!(std::ptr::eq(
+        fr,
+        DUPLICATES.get_dir("fr").unwrap().as_root()
+    ));
+
+    let live = LIVE.get_dir("fr").unwrap().as_root();
+    let paths: Vec<_> = live.current_entries().iter().map(|e| e.path()).collect();
+    assert_eq!(paths, [Path::new("LICENSE"), Path::new("greeting.txt")]);
+    assert_eq!(
+        live.get_file("greeting.txt").unwrap().contents(),
+        b"Bonjour\n"
+    );
+}
+
+#[test]
+fn view_a_sub_directory_with_overrides() {
+    let temp = TempDir::new().unwrap();
+    std::fs::create_dir(temp.path().join("fr")).unwrap();
+    std::fs::write(temp.path().join("fr").join("greeting.txt"), "Salut").unwrap();
+    let assets: &'static Dir<'static> =
+        Box::leak(Box::new(EMBEDDED.with_override_root(temp.path())));
+
+    let fr = assets.get_dir("fr").unwrap().as_root();
+
+    assert_eq!(fr.get_file("greeting.txt").unwrap().contents(), b"Salut");
+    assert_eq!(
+        fr.get_file("LICENSE").unwrap().contents(),
+        EMBEDDED.get_file("fr/LICENSE").unwrap().contents()
+    );
+}
+
+#[test]
+fn navigate_to_parent_directories() {
+    let greeting = DUPLICATES.get_file("fr/greeting.txt").unwrap();
+
+    let parent = DUPLICATES.parent_of(greeting.path()).unwrap();
+    assert_eq!(parent.path(), Path::new("fr"));
+    assert!(parent.contains("fr/LICENSE"));
+
+    let ancestors: Vec<_> = DUPLICATES
+        .ancestors_of("fr/greeting.txt")
+        .map(|d| d.path())
+        .collect();
+    assert_eq!(ancestors, [Path::new("fr"), Path::new("")]);
+
+    assert_eq!(DUPLICATES.parent_of("fr").unwrap().path(), Path::new(""));
+    assert!(DUPLICATES.parent_of("fr/missing.txt").is_none());
+    assert!(DUPLICATES.parent_of("").is_none());
+
+    let fr = DUPLICATES.get_dir("fr").unwrap();
+    assert!(std::ptr::eq(fr.parent_of("fr/LICENSE").unwrap(), fr));
+    assert_eq!(fr.ancestors_of("fr/LICENSE").count(), 1);
+    assert_eq!(fr.ancestors_of("en/LICENSE").count(), 0);
+
+    let ancestors: Vec<_> = PARENT_DIR
+        .ancestors_of("tests/fixtures/duplicates/fr/LICENSE")
+        .map(|d| d.path())
+        .collect();
+    assert_eq!(
+        ancestors,
+        [
+            Path::new("tests/fixtures/duplicates/fr"),
+            Path::new("tests/fixtures/duplicates"),
+            Path::new("tests/fixtures"),
+            Path::new("tests"),
+            Path::new(""),
+        ]
+    );
+}
+
+#[test]
+fn same_path_in_several_embedded_directories() {
+    let default_logo = DEFAULTS.get_file("logo.txt").unwrap();
+    let product_logo = PRODUCT.get_file("logo.txt").unwrap();
+
+    assert_eq!(default_logo.contents_utf8().unwrap(), "default logo\n");
+    assert_eq!(product_logo.contents_utf8().unwrap(), "product logo\n");
+    assert!(!default_logo.shares_contents_with(product_logo));
+}
+
+#[test]
+fn rewrite_paths_when_embedding() {
+    let index = REWRITTEN.get_file("static/index.html").unwrap();
+    assert_eq!(index.path(), Path::new("static/index.html"));
+    assert_eq!(index.contents_utf8().unwrap(), "<h1>Hello</h1>\n");
+
+    let js = REWRITTEN.get_dir("static/js").unwrap();
+    assert_eq!(js.path(), Path::new("static/js"));
+    let app = js.get_file("static/js/app.js").unwrap();
+    assert_eq!(app.contents_utf8().unwrap(), "console.log(1)\n");
+
+    assert!(!REWRITTEN.contains("dist"));
+    assert!(!REWRITTEN.contains("static/js/app.min.js"));
+}
+
+#[test]
+#[cfg(unix)]
+fn preserve_symlinks() {
+    let readme = SYMLINKS
+        .get_entry("README.md")
+        .unwrap()
+        .as_symlink()
+        .unwrap();
+    assert_eq!(readme.target(), Path::new("../README.md"));
+
+    let tmpdir = TempDir::new().unwrap();
+    SYMLINKS.extract(tmpdir.path()).unwrap();
+
+    let link = tmpdir.path().join("README.md");
+    assert!(link.symlink_metadata().unwrap().file_type().is_symlink());
+    assert_eq!(link.read_link().unwrap(), Path::new("../README.md"));
+    assert!(tmpdir.path().join("src/lib.rs").is_file());
+}
+
+#[test]
+fn identical_files_share_storage() {
+    let en = DUPLICATES.get_file("en/LICENSE").unwrap();
+    let fr = DUPLICATES.get_file("fr/LICENSE").unwrap();
+    let greeting = DUPLICATES.get_file("fr/greeting.txt").unwrap();
+
+    assert_eq!(en.contents(), fr.contents());
+    assert!(en.shares_contents_with(en));
+    assert!(!en.shares_contents_with(greeting));
+    // Nothing is embedded in debug builds
+    assert_eq!(en.shares_contents_with(fr), embedded_by_default());
+}
+
+#[test]
+fn choose_whether_to_embed() {
+    let embedded = (
+        EMBEDDED.get_file("en/LICENSE").unwrap(),
+        EMBEDDED.get_file("fr/LICENSE").unwrap(),
+    );
+    let on_disk = (
+        ON_DISK.get_file("en/LICENSE").unwrap(),
+        ON_DISK.get_file("fr/LICENSE").unwrap(),
+    );
+
+    assert!(embedded.0.shares_contents_with(embedded.1));
+    assert_eq!(embedded.0.contents(), on_disk.0.contents());
+    // The "embed" feature guarantees everything is embedded
+    assert_eq!(
+        on_disk.0.shares_contents_with(on_disk.1),
+        cfg!(feature = "embed")
+    );
+}
+
+#[test]
+#[cfg(feature = "sha256")]
+fn hashes_are_calculated_at_compile_time() {
+    let en = HASHED.get_file("en/LICENSE").unwrap();
+    let fr = HASHED.get_dir("fr").unwrap();
+
+    let hash = en.hash().unwrap();
+    assert_eq!(hash.algorithm(), include_dir::HashAlgorithm::Sha256);
+    assert_eq!(
+        hash.to_hex(),
+        "2b344239c8627a548ac2b1d855356efef1836a8981b19124a3e1c50f9307fde1"
+    );
+    assert_eq!(fr.get_file("fr/LICENSE").unwrap().hash(), Some(hash));
+    // "fr" contains an extra file, so the directories have different digests
+    let en_dir = HASHED.get_dir("en").unwrap();
+    assert_ne!(en_dir.digest(), fr.digest());
+    assert!(HASHED.digest().is_some());
+}
+
+#[test]
+#[cfg(feature = "mime")]
+fn classify_files_at_compile_time() {
+    let index = REWRITTEN.get_file("static/index.html").unwrap();
+    let license = DUPLICATES.get_file("en/LICENSE").unwrap();
+
+    assert_eq!(index.mime_type(), Some("text/html"));
+    assert!(index.is_text());
+    assert_eq!(license.mime_type(), Some("text/plain"));
+    assert!(license.is_text());
+}
+
+#[test]
+#[cfg(feature = "gzip")]
+fn transparently_decompress_files() {
+    let lib_rs = GZIPPED.get_file("lib.rs").unwrap();
+    let expected = std::fs::read(Path::new(env!("CARGO_MANIFEST_DIR")).join("src/lib.rs")).unwrap();
+
+    assert_eq!(lib_rs.contents(), expected);
+
+    if embedded_by_default() {
+        let (compression, compressed) = lib_rs.compressed_contents().unwrap();
+        assert_eq!(compression, include_dir::Compression::Gzip);
+        assert!(compressed.len() < expected.len());
+    }
+}
+
+#[test]
+fn pick_up_changes_to_files_on_disk() {
+    let temp = TempDir::new().unwrap();
+    let path = temp.path().join("config.toml");
+    std::fs::write(&path, "first").unwrap();
+    let root = static_path(temp.path());
+    let file = File::new("config.toml", &[]).with_root(root);
+
+    assert_eq!(file.contents_utf8(), Some("first"));
+
+    std::fs::write(&path, "second").unwrap();
+    let later = std::time::SystemTime::now() + std::time::Duration::from_secs(60);
+    let later = filetime::FileTime::from_system_time(later);
+    filetime::set_file_mtime(&path, later).unwrap();
+    assert_eq!(file.contents_utf8(), Some("second"));
+
+    // pretend the edit didn't touch the modification time
+    std::fs::write(&path, "third").unwrap();
+    filetime::set_file_mtime(&path, later).unwrap();
+    include_dir::reload();
+    assert_eq!(file.contents_utf8(), Some("third"));
+}
+
+#[test]
+fn live_directories_behave_like_embedded_ones() {
+    let greeting = LIVE.get_file("fr/greeting.txt").unwrap();
+
+    assert_eq!(greeting.contents_utf8(), Some("Bonjour\n"));
+    assert_eq!(
+        all_files(&LIVE)
+            .iter()
+            .map(|f| f.path())
+            .collect::<Vec<_>>(),
+        all_files(&DUPLICATES)
+            .iter()
+            .map(|f| f.path())
+            .collect::<Vec<_>>()
+    );
+
+    // Directories listed at runtime come with their entries, just like the
+    // ones generated by the macro
+    let fr = LIVE.get_dir("fr").unwrap();
+    assert_eq!(
+        fr.entries().iter().map(|e| e.path()).collect::<Vec<_>>(),
+        [Path::new("fr/LICENSE"), Path::new("fr/greeting.txt")]
+    );
+}
+
+#[test]
+#[cfg(unix)]
+fn live_symlinks_back_up_the_tree_arent_followed_forever() {
+    let temp = TempDir::new().unwrap();
+    std::fs::create_dir(temp.path().join("nested")).unwrap();
+    std::os::unix::fs::symlink(temp.path(), temp.path().join("nested").join("up")).unwrap();
+    let root = static_path(temp.path());
+    let dir = Dir::new("", &[]).with_root(root);
+
+    let nested = dir.get_dir("nested").unwrap();
+    assert!(nested.get_dir("nested/up").unwrap().entries().is_empty());
+    assert!(dir.contains("nested/up/nested/up"));
+}
+
+#[test]
+fn list_files_added_after_compiling() {
+    let temp = TempDir::new().unwrap();
+    std::fs::write(temp.path().join("a.txt"), "a").unwrap();
+    let root = static_path(temp.path());
+    let dir = Dir::new("", &[]).with_root(root);
+
+    assert_eq!(all_files(&dir).len(), 1);
+
+    std::fs::create_dir(temp.path().join("nested")).unwrap();
+    std::fs::write(temp.path().join("nested").join("b.txt"), "b").unwrap();
+    // make sure the change is noticed, even on filesystems with a coarse
+    // modification time
+    include_dir::reload();
+
+    assert_eq!(dir.get_file("nested/b.txt").unwrap().contents(), b"b");
+    assert!(dir.contains("a.txt"));
+}
+
+#[test]
+fn handles_keep_old_versions_alive() {
+    let temp = TempDir::new().unwrap();
+    let path = temp.path().join("shader.glsl");
+    std::fs::write(&path, "v1").unwrap();
+    let root = static_path(temp.path());
+    let file = File::new("shader.glsl", &[]).with_root(root);
+
+    let first = file.read().unwrap();
+    std::fs::write(&path, "v2").unwrap();
+    include_dir::reload();
+    let second = file.read().unwrap();
+
+    assert_eq!(&*first, b"v1");
+    assert_eq!(&*second, b"v2");
+    assert_eq!(
+        DUPLICATES
+            .get_file("en/LICENSE")
+            .unwrap()
+            .read()
+            .unwrap()
+            .as_slice(),
+        DUPLICATES.get_file("en/LICENSE").unwrap().contents()
+    );
+}
+
+#[test]
+fn override_embedded_files_at_runtime() {
+    let temp = TempDir::new().unwrap();
+    std::fs::create_dir(temp.path().join("en")).unwrap();
+    std::fs::write(temp.path().join("en").join("LICENSE"), "Modded").unwrap();
+    std::fs::write(temp.path().join("en").join("extra.txt"), "New").unwrap();
+
+    let dir = DUPLICATES.with_override_root(temp.path());
+
+    assert_eq!(dir.get_file("en/LICENSE").unwrap().contents(), b"Modded");
+    assert_eq!(dir.get_file("en/extra.txt").unwrap().contents(), b"New");
+    assert_eq!(
+        dir.get_file("fr/LICENSE").unwrap().contents(),
+        DUPLICATES.get_file("fr/LICENSE").unwrap().contents()
+    );
+    let en = dir.get_dir("en").unwrap();
+    assert_eq!(en.get_file("en/LICENSE").unwrap().contents(), b"Modded");
+    assert!(!DUPLICATES.contains("en/extra.txt"));
+
+    // Nothing in fr/ was overridden, so it falls back to the embedded files
+    let fr = dir.get_dir("fr").unwrap();
+    assert_eq!(
+        fr.get_file("fr/LICENSE").unwrap().contents(),
+        DUPLICATES.get_file("fr/LICENSE").unwrap().contents()
+    );
+}
+
+#[test]
+fn directories_can_share_an_override_root() {
+    let temp = TempDir::new().unwrap();
+
+    let defaults = DEFAULTS.with_override_root(temp.path());
+    let product = PRODUCT.with_override_root(temp.path());
+
+    let img = defaults.get_dir("img").unwrap();
+    assert!(img.contains("img/a.txt"));
+    assert!(!img.contains("img/b.txt"));
+
+    let img = product.get_dir("img").unwrap();
+    assert!(img.contains("img/b.txt"));
+    assert!(!img.contains("img/a.txt"));
+}
+
+#[test]
+fn overrides_cant_escape_their_root() {
+    let temp = TempDir::new().unwrap();
+    std::fs::create_dir(temp.path().join("mods")).unwrap();
+    std::fs::write(temp.path().join("secret.txt"), "secret").unwrap();
+
+    let assets = EMBEDDED.with_override_root(temp.path().join("mods"));
+
+    assert!(!assets.contains("../secret.txt"));
+}
+
+#[test]
+fn move_the_root_at_runtime() {
+    let temp = TempDir::new().unwrap();
+    std::fs::create_dir(temp.path().join("en")).unwrap();
+    std::fs::write(temp.path().join("en").join("LICENSE"), "Moved").unwrap();
+
+    RELOCATABLE.set_root(temp.path());
+
+    let license = RELOCATABLE.get_file("en/LICENSE").unwrap();
+    if cfg!(feature = "embed") {
+        assert_eq!(
+            license.contents(),
+            DUPLICATES.get_file("en/LICENSE").unwrap().contents()
+        );
+    } else {
+        assert_eq!(license.contents(), b"Moved");
+    }
+}
+
+#[test]
+fn move_the_root_with_an_environment_variable() {
+    let temp = TempDir::new().unwrap();
+    std::fs::create_dir(temp.path().join("fr")).unwrap();
+    std::fs::write(temp.path().join("fr").join("greeting.txt"), "Salut").unwrap();
+    std::fs::write(temp.path().join("fr").join("new.txt"), "").unwrap();
+
+    std::env::set_var("INCLUDE_DIR_ROOT_FROM_ENV", temp.path());
+
+    let greeting = FROM_ENV.get_file("fr/greeting.txt").unwrap();
+    if cfg!(feature = "embed") {
+        assert_eq!(greeting.contents(), b"Bonjour\n");
+    } else {
+        assert_eq!(greeting.contents(), b"Salut");
+        assert!(FROM_ENV.contains("fr/new.txt"));
+    }
+}
+
+#[test]
+#[cfg(feature = "watch")]
+fn watch_for_changes() {
+    use include_dir::Change;
+    use std::time::Duration;
+
+    let temp = TempDir::new().unwrap();
+    std::fs::create_dir(temp.path().join("nested")).unwrap();
+    let root = temp.path().canonicalize().unwrap();
+    let root = static_path(&root);
+    let dir = Dir::new("", &[]).with_root(root);
+    let nested = dir.get_dir("nested").unwrap();
+
+    let (_watcher, changes) = nested.watch_channel().unwrap();
+    std::fs::write(temp.path().join("ignored.txt"), "").unwrap();
+    std::fs::write(temp.path().join("nested").join("new.txt"), "").unwrap();
+
+    let change = changes.recv_timeout(Duration::from_secs(10)).unwrap();
+    assert_eq!(change, Change::Created("nested/new.txt".into()));
+}
+
+#[test]
+#[cfg(feature = "watch")]
+fn watch_a_sub_directory_as_its_own_root() {
+    use include_dir::Change;
+    use std::time::Duration;
+
+    let temp = TempDir::new().unwrap();
+    std::fs::create_dir(temp.path().join("nested")).unwrap();
+    let root = temp.path().canonicalize().unwrap();
+    let root = static_path(&root);
+    let dir: &'static Dir<'static> = Box::leak(Box::new(Dir::new("", &[]).with_root(root)));
+    let nested = dir.get_dir("nested").unwrap().as_root();
+
+    let (_watcher, changes) = nested.watch_channel().unwrap();
+    std::fs::write(temp.path().join("nested").join("new.txt"), "").unwrap();
+
+    let change = changes.recv_timeout(Duration::from_secs(10)).unwrap();
+    assert_eq!(change, Change::Created("new.txt".into()));
+}
+
+#[test]
+#[cfg(feature = "watch")]
+fn only_watch_files_which_were_included() {
+    use include_dir::Change;
+    use std::time::Duration;
+
+    let temp = TempDir::new().unwrap();
+    std::fs::create_dir(temp.path().join("target")).unwrap();
+    std::fs::create_dir(temp.path().join("src")).unwrap();
+    std::fs::write(temp.path().join("included.txt"), "").unwrap();
+    std::fs::write(temp.path().join("src").join("lib.rs"), "").unwrap();
+    let root = temp.path().canonicalize().unwrap();
+    let root = static_path(&root);
+    let src: &'static [_] = Box::leak(Box::new([include_dir::DirEntry::File(
+        File::new("src/lib.rs", &[]).with_root(root),
+    )]));
+    let files: &'static [_] = Box::leak(Box::new([
+        include_dir::DirEntry::File(File::new("included.txt", &[]).with_root(root)),
+        include_dir::DirEntry::Dir(Dir::new("src", src)),
+    ]));
+    let dir = Dir::new("", files);
+
+    let (_watcher, changes) = dir.watch_channel().unwrap();
+    std::fs::write(temp.path().join("target").join("build.log"), "").unwrap();
+    std::fs::write(temp.path().join("excluded.txt"), "").unwrap();
+    std::fs::remove_file(temp.path().join("included.txt")).unwrap();
+
+    let change = changes.recv_timeout(Duration::from_secs(10)).unwrap();
+    assert_eq!(change, Change::Removed("included.txt".into()));
+
+    // Files in sub-directories are still watched
+    std::fs::remove_file(temp.path().join("src").join("lib.rs")).unwrap();
+
+    let change = changes.recv_timeout(Duration::from_secs(10)).unwrap();
+    assert_eq!(change, Change::Removed("src/lib.rs".into()));
+}
+
+#[test]
+#[cfg(feature = "watch")]
+fn nothing_to_watch_when_embedded() {
+    let (_watcher, changes) = EMBEDDED.watch_channel().unwrap();
+
+    assert!(changes.recv().is_err());
+}
+
+#[test]
+fn report_files_which_cant_be_read() {
+    let temp = TempDir::new().unwrap();
+    std::fs::write(temp.path().join("binary.dat"), [0xff, 0xfe]).unwrap();
+    let root = static_path(temp.path());
+    let deleted = File::new("deleted.txt", &[]).with_root(root);
+    let binary = File::new("binary.dat", &[]).with_root(root);
+
+    let err = deleted.try_contents().unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::NotFound);
+    assert!(err.to_string().contains("deleted.txt"), "{}", err);
+
+    let err = binary.try_contents_utf8().unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    assert_eq!(binary.try_contents().unwrap(), [0xff, 0xfe]);
+}
+
 #[test]
 fn extract_all_files() {
     let tmpdir = TempDir::new().unwrap();
@@ -44,11 +729,12 @@ fn validate_included(dir: &Dir<'_>, path: &Path, root: &Path) {
 
 // Validates that all files in the inclusion were extracted to the filesystem
 fn validate_extracted(dir: &Dir, path: &Path) {
-    // Check if all the subdirectories exist, recursing on each
+    // Check if all the subdirectories exist, recursing on each. Entry paths
+    // are relative to the root, so the base path stays the same.
     for subdir in dir.dirs() {
-        let subdir_path = path.join(dir.path());
+        let subdir_path = path.join(subdir.path());
         assert!(subdir_path.exists());
-        validate_extracted(subdir, &subdir_path);
+        validate_extracted(subdir, path);
     }
 
     // Check if the files at the root of this directory exist
@@ -57,3 +743,24 @@ fn validate_extracted(dir: &Dir, path: &Path) {
         assert!(file_path.exists());
     }
 }
+
+/// Does `include_dir!()` embed files when no `mode` is given?
+fn embedded_by_default() -> bool {
+    cfg!(feature = "embed") || !cfg!(debug_assertions)
+}
+
+/// Get a path that can be passed to `File::with_root()` and
+/// `Dir::with_root()`, which need a string that outlives the test.
+fn static_path(path: &Path) -> &'static str {
+    Box::leak(path.to_str().unwrap().to_string().into_boxed_str())
+}
+
+fn all_files<'a>(dir: &Dir<'a>) -> Vec<&'a File<'a>> {
+    let mut files: Vec<_> = dir.files().collect();
+
+    for subdir in dir.dirs() {
+        files.extend(all_files(subdir));
+    }
+
+    files
+}
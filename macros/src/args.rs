@@ -0,0 +1,387 @@
+//! Parsing the arguments passed to `include_dir!()`.
+
+use crate::{
+    budget::Budget,
+    compress::Compression,
+    filter::{Filter, SpecialFiles, Symlinks},
+    hash::HashAlgorithm,
+    mode::Mode,
+    rewrite::Rewrites,
+    tree::OnConflict,
+};
+use glob::Pattern;
+use proc_macro::{Delimiter, Literal, Spacing, TokenStream, TokenTree};
+
+/// The arguments passed to `include_dir!()`.
+///
+/// The macro accepts one or more paths followed by any number of
+/// `key = value` options, for example
+/// `include_dir!("assets", exclude = ["**/*.psd"], max_depth = 3)`.
+#[derive(Debug)]
+pub(crate) struct Args {
+    /// The directories to embed, in increasing order of precedence.
+    pub(crate) paths: Vec<String>,
+    pub(crate) filter: Filter,
+    pub(crate) rewrites: Rewrites,
+    pub(crate) on_conflict: OnConflict,
+    pub(crate) budget: Budget,
+    pub(crate) compression: Option<Compression>,
+    pub(crate) hash: Option<HashAlgorithm>,
+    /// List directories from disk at runtime in debug builds.
+    pub(crate) live: bool,
+    pub(crate) mode: Mode,
+    /// Used to move the root directory at runtime.
+    pub(crate) name: Option<String>,
+}
+
+impl Args {
+    pub(crate) fn parse(input: TokenStream) -> Args {
+        let mut args = Args {
+            paths: Vec::new(),
+            filter: Filter::default(),
+            rewrites: Rewrites::default(),
+            on_conflict: OnConflict::default(),
+            budget: Budget::default(),
+            compression: None,
+            hash: None,
+            live: false,
+            mode: Mode::default(),
+            name: None,
+        };
+
+        let mut arguments = split_arguments(input).into_iter().peekable();
+
+        // Paths come first, followed by the options
+        while let Some([TokenTree::Literal(lit)]) = arguments.peek().map(Vec::as_slice) {
+            args.paths.push(unwrap_string_literal(lit));
+            arguments.next();
+        }
+
+        if args.paths.is_empty() {
+            panic!("The first argument to include_dir!() must be a string literal");
+        }
+
+        for argument in arguments {
+            let (key, value) = parse_option(&argument);
+            args.apply(&key, value);
+        }
+
+        args
+    }
+
+    fn apply(&mut self, key: &str, value: Value) {
+        match key {
+            "include" => self.filter.include = patterns(key, value),
+            "exclude" => self.filter.exclude = patterns(key, value),
+            "max_depth" => self.filter.max_depth = Some(value.into_int(key) as usize),
+            "gitignore" => {
+                let gitignore = value.into_bool(key);
+                if gitignore && !cfg!(feature = "gitignore") {
+                    panic!("Using \"gitignore\" requires the \"gitignore\" feature on include_dir");
+                }
+                self.filter.gitignore = gitignore;
+            }
+            "skip_hidden" => self.filter.skip_hidden = value.into_bool(key),
+            "symlinks" => {
+                self.filter.symlinks = match value.into_str(key).as_str() {
+                    "follow" => Symlinks::Follow,
+                    "skip" => Symlinks::Skip,
+                    "error" => Symlinks::Error,
+                    "preserve" => Symlinks::Preserve,
+                    other => panic!(
+                        "Expected \"symlinks\" to be \"follow\", \"skip\", \"error\" or \"preserve\", found \"{}\"",
+                        other
+                    ),
+                }
+            }
+            "special_files" => {
+                self.filter.special_files = match value.into_str(key).as_str() {
+                    "error" => SpecialFiles::Error,
+                    "skip" => SpecialFiles::Skip,
+                    other => panic!(
+                        "Expected \"special_files\" to be \"error\" or \"skip\", found \"{}\"",
+                        other
+                    ),
+                }
+            }
+            "flatten" => self.rewrites.flatten = patterns(key, value),
+            "rename_extensions" => {
+                self.rewrites.rename_extensions = value
+                    .into_list(key)
+                    .into_iter()
+                    .map(|item| item.into_pair(key))
+                    .map(|(from, to)| {
+                        let from = from.into_str(key);
+                        let to = to.into_str(key);
+                        (
+                            from.trim_start_matches('.').to_string(),
+                            to.trim_start_matches('.').to_string(),
+                        )
+                    })
+                    .collect()
+            }
+            "max_file_size" => self.budget.max_file_size = Some(value.into_int(key)),
+            "max_total_size" => self.budget.max_total_size = Some(value.into_int(key)),
+            "compression" => self.compression = Some(Compression::parse(&value.into_str(key))),
+            "hash" => self.hash = Some(HashAlgorithm::parse(&value.into_str(key))),
+            "live" => self.live = value.into_bool(key),
+            "name" => {
+                let name = value.into_str(key);
+                if name.is_empty() {
+                    panic!("\"name\" can't be empty");
+                }
+                self.name = Some(name);
+            }
+            "mode" => self.mode = Mode::parse(key, &value.into_str(key)),
+            "mount" => self.rewrites.mount = Some(value.into_str(key)),
+            "on_conflict" => {
+                self.on_conflict = match value.into_str(key).as_str() {
+                    "override" => OnConflict::Override,
+                    "error" => OnConflict::Error,
+                    other => panic!(
+                        "Expected \"on_conflict\" to be \"override\" or \"error\", found \"{}\"",
+                        other
+                    ),
+                }
+            }
+            _ => panic!("Unknown include_dir!() option, \"{}\"", key),
+        }
+    }
+}
+
+/// A value on the right-hand side of a `key = value` option.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Value {
+    Str(String),
+    Int(u64),
+    Bool(bool),
+    List(Vec<Value>),
+    /// Written as `left => right`.
+    Pair(Box<Value>, Box<Value>),
+}
+
+impl Value {
+    fn into_str(self, key: &str) -> String {
+        match self {
+            Value::Str(s) => s,
+            other => panic!("Expected \"{}\" to be a string, found {:?}", key, other),
+        }
+    }
+
+    fn into_int(self, key: &str) -> u64 {
+        match self {
+            Value::Int(i) => i,
+            other => panic!("Expected \"{}\" to be an integer, found {:?}", key, other),
+        }
+    }
+
+    fn into_bool(self, key: &str) -> bool {
+        match self {
+            Value::Bool(b) => b,
+            other => panic!("Expected \"{}\" to be a boolean, found {:?}", key, other),
+        }
+    }
+
+    fn into_pair(self, key: &str) -> (Value, Value) {
+        match self {
+            Value::Pair(left, right) => (*left, *right),
+            other => panic!(
+                "Expected \"{}\" to contain `from => to` pairs, found {:?}",
+                key, other
+            ),
+        }
+    }
+
+    fn into_list(self, key: &str) -> Vec<Value> {
+        match self {
+            Value::List(items) => items,
+            // Let people write `exclude = "*.psd"` instead of `exclude = ["*.psd"]`
+            single @ Value::Str(_) => vec![single],
+            other => panic!("Expected \"{}\" to be a list, found {:?}", key, other),
+        }
+    }
+}
+
+fn patterns(key: &str, value: Value) -> Vec<Pattern> {
+    value
+        .into_list(key)
+        .into_iter()
+        .map(|item| {
+            let glob = item.into_str(key);
+            Pattern::new(&glob).unwrap_or_else(|e| {
+                panic!("Invalid glob pattern \"{}\" in \"{}\": {}", glob, key, e)
+            })
+        })
+        .collect()
+}
+
+/// Split the macro's input into its comma-separated arguments.
+fn split_arguments(input: TokenStream) -> Vec<Vec<TokenTree>> {
+    let mut arguments = Vec::new();
+    let mut current = Vec::new();
+
+    for token in flatten(input) {
+        match token {
+            TokenTree::Punct(ref p) if p.as_char() == ',' => {
+                arguments.push(std::mem::take(&mut current));
+            }
+            other => current.push(other),
+        }
+    }
+
+    if !current.is_empty() {
+        arguments.push(current);
+    }
+
+    arguments
+}
+
+/// Remove the invisible groups introduced when the macro is invoked from
+/// inside a `macro_rules!` macro.
+fn flatten(input: TokenStream) -> Vec<TokenTree> {
+    let mut tokens = Vec::new();
+
+    for token in input {
+        match token {
+            TokenTree::Group(g) if g.delimiter() == Delimiter::None => {
+                tokens.extend(flatten(g.stream()))
+            }
+            other => tokens.push(other),
+        }
+    }
+
+    tokens
+}
+
+fn parse_option(tokens: &[TokenTree]) -> (String, Value) {
+    match tokens {
+        [TokenTree::Ident(key), TokenTree::Punct(eq), value @ ..]
+            if eq.as_char() == '=' && !value.is_empty() =>
+        {
+            let key = key.to_string();
+            let value = parse_value(&key, value);
+            (key, value)
+        }
+        _ => panic!(
+            "Expected an option like `key = value`, found `{}`",
+            tokens.iter().cloned().collect::<TokenStream>()
+        ),
+    }
+}
+
+fn parse_value(key: &str, tokens: &[TokenTree]) -> Value {
+    if let Some(arrow) = tokens.windows(2).position(is_fat_arrow) {
+        let left = parse_value(key, &tokens[..arrow]);
+        let right = parse_value(key, &tokens[arrow + 2..]);
+        return Value::Pair(Box::new(left), Box::new(right));
+    }
+
+    match tokens {
+        [TokenTree::Literal(lit)] => parse_literal(key, lit),
+        [TokenTree::Ident(ident)] => match ident.to_string().as_str() {
+            "true" => Value::Bool(true),
+            "false" => Value::Bool(false),
+            other => panic!("Unable to parse the value for \"{}\": `{}`", key, other),
+        },
+        [TokenTree::Group(g)] if g.delimiter() == Delimiter::Bracket => {
+            let items = split_arguments(g.stream())
+                .iter()
+                .map(|item| parse_value(key, item))
+                .collect();
+            Value::List(items)
+        }
+        [TokenTree::Group(g)] if g.delimiter() == Delimiter::None => {
+            parse_value(key, &flatten(g.stream()))
+        }
+        _ => panic!(
+            "Unable to parse the value for \"{}\": `{}`",
+            key,
+            tokens.iter().cloned().collect::<TokenStream>()
+        ),
+    }
+}
+
+fn is_fat_arrow(tokens: &[TokenTree]) -> bool {
+    match tokens {
+        [TokenTree::Punct(eq), TokenTree::Punct(gt)] => {
+            eq.as_char() == '=' && eq.spacing() == Spacing::Joint && gt.as_char() == '>'
+        }
+        _ => false,
+    }
+}
+
+fn parse_literal(key: &str, lit: &Literal) -> Value {
+    let repr = lit.to_string();
+
+    if repr.starts_with('"') {
+        return Value::Str(unwrap_string_literal(lit));
+    }
+
+    Value::Int(parse_int(key, &repr))
+}
+
+/// Parse an integer literal, which may only use decimal digits, underscores
+/// and a `u64` or `usize` suffix.
+fn parse_int(key: &str, repr: &str) -> u64 {
+    let digits = ["u64", "usize"]
+        .iter()
+        .find_map(|suffix| repr.strip_suffix(suffix))
+        .unwrap_or(repr);
+
+    let valid = digits.starts_with(|c: char| c.is_ascii_digit())
+        && digits.chars().all(|c| c.is_ascii_digit() || c == '_');
+
+    match digits.replace('_', "").parse() {
+        Ok(i) if valid => i,
+        _ => panic!("Expected \"{}\" to be an integer, found `{}`", key, repr),
+    }
+}
+
+pub(crate) fn unwrap_string_literal(lit: &Literal) -> String {
+    let mut repr = lit.to_string();
+    if !repr.starts_with('"') || !repr.ends_with('"') {
+        panic!("Expected a string literal, found `{}`", repr)
+    }
+
+    repr.remove(0);
+    repr.pop();
+
+    repr
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_integers() {
+        assert_eq!(parse_int("max_depth", "3"), 3);
+        assert_eq!(parse_int("max_file_size", "1_000_000"), 1_000_000);
+        assert_eq!(parse_int("max_file_size", "1024u64"), 1024);
+        assert_eq!(parse_int("max_depth", "2usize"), 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "found `1e9`")]
+    fn reject_floats_written_as_exponents() {
+        parse_int("max_file_size", "1e9");
+    }
+
+    #[test]
+    #[should_panic(expected = "found `1.5`")]
+    fn reject_fractions() {
+        parse_int("max_depth", "1.5");
+    }
+
+    #[test]
+    #[should_panic(expected = "found `0x10`")]
+    fn reject_other_bases() {
+        parse_int("max_depth", "0x10");
+    }
+
+    #[test]
+    #[should_panic(expected = "found `10u8`")]
+    fn reject_other_suffixes() {
+        parse_int("max_depth", "10u8");
+    }
+}
@@ -0,0 +1,245 @@
+//! Making sure identical file contents are only embedded once.
+
+use crate::compress::Compression;
+use proc_macro2::{Ident, Literal, Span, TokenStream};
+use quote::quote;
+use std::{
+    collections::{hash_map::DefaultHasher, HashMap},
+    hash::{Hash, Hasher},
+    path::{Path, PathBuf},
+};
+
+/// The unique file contents seen so far, each of which will be emitted as a
+/// `static` that every [`File`][file] with those contents refers to.
+///
+/// A `static` (unlike a `const`) is guaranteed to have a single address, so
+/// the contents really are only stored once.
+///
+/// [file]: https://docs.rs/include_dir/*/include_dir/struct.File.html
+#[derive(Debug, Default)]
+pub(crate) struct Blobs {
+    blobs: Vec<Blob>,
+    /// Indices into `blobs`, keyed by a hash of the uncompressed contents
+    /// and their length.
+    by_hash: HashMap<(u64, usize), Vec<usize>>,
+    /// Files which were read without being passed to `include_bytes!()`
+    /// (e.g. because they were compressed, or had the same contents as
+    /// another file), and still need to tell the compiler to rebuild when
+    /// they change.
+    untracked: Vec<PathBuf>,
+}
+
+#[derive(Debug)]
+struct Blob {
+    /// The file these contents were first read from.
+    path: PathBuf,
+    /// An expression for the (possibly compressed) contents, as a `&[u8; N]`.
+    literal: TokenStream,
+    /// How many bytes `literal` contains.
+    len: usize,
+    compression: Option<Compression>,
+}
+
+impl Blobs {
+    /// Get the name of the `static` holding a file's contents, plus the
+    /// compression that was applied to it and whether these contents were
+    /// seen for the first time.
+    pub(crate) fn add(
+        &mut self,
+        path: &Path,
+        compression: Option<Compression>,
+    ) -> (Ident, Option<Compression>, bool) {
+        let contents = crate::read_file(path);
+        let key = (hash(&contents), contents.len());
+
+        let candidates = self.by_hash.entry(key).or_default();
+
+        // Hash collisions are unlikely, but it's cheap enough to make sure
+        for &index in candidates.iter() {
+            let blob = &self.blobs[index];
+            if crate::read_file(&blob.path) == contents {
+                self.untracked.push(path.to_path_buf());
+                return (blob_name(index), blob.compression, false);
+            }
+        }
+
+        let (literal, len, compression) = literal(path, contents, compression);
+        if compression.is_some() {
+            self.untracked.push(path.to_path_buf());
+        }
+        let index = self.blobs.len();
+        self.blobs.push(Blob {
+            path: path.to_path_buf(),
+            literal,
+            len,
+            compression,
+        });
+        candidates.push(index);
+
+        (blob_name(index), compression, true)
+    }
+
+    /// Make the compiler rebuild when a file changes, for files which were
+    /// read at compile time without embedding their contents.
+    pub(crate) fn track(&mut self, path: &Path) {
+        self.untracked.push(path.to_path_buf());
+    }
+
+    /// Declare a `static` for each blob.
+    pub(crate) fn to_tokens(&self) -> TokenStream {
+        let statics = self.blobs.iter().enumerate().map(|(i, blob)| {
+            let name = blob_name(i);
+            let literal = &blob.literal;
+            let len = blob.len;
+            quote!(static #name: [u8; #len] = *#literal;)
+        });
+
+        // An unused constant makes the compiler track the file without
+        // putting its contents in the binary
+        let tracked = self
+            .untracked
+            .iter()
+            .filter_map(|path| absolute(path))
+            .map(|abs| {
+                quote!(
+                    const _: &[u8] = include_bytes!(#abs);
+                )
+            });
+
+        quote!(#(#statics)* #(#tracked)*)
+    }
+
+    /// How many unique blobs there are.
+    #[cfg(test)]
+    fn len(&self) -> usize {
+        self.blobs.len()
+    }
+}
+
+fn hash(contents: &[u8]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    contents.hash(&mut hasher);
+    hasher.finish()
+}
+
+fn blob_name(index: usize) -> Ident {
+    Ident::new(&format!("BLOB_{}", index), Span::call_site())
+}
+
+fn literal(
+    path: &Path,
+    contents: Vec<u8>,
+    compression: Option<Compression>,
+) -> (TokenStream, usize, Option<Compression>) {
+    if let Some(c) = compression {
+        let compressed = c.compress(&contents);
+
+        // Don't bother if compressing would make the file bigger
+        if compressed.len() < contents.len() {
+            let literal = Literal::byte_string(&compressed);
+            return (quote!(#literal), compressed.len(), Some(c));
+        }
+    }
+
+    // Prefer include_bytes!() because it tells the compiler to rebuild when
+    // the file changes
+    let tokens = match absolute(path) {
+        Some(abs) => quote!(include_bytes!(#abs)),
+        None => {
+            let literal = Literal::byte_string(&contents);
+            quote!(#literal)
+        }
+    };
+
+    (tokens, contents.len(), None)
+}
+
+/// The path to use with `include_bytes!()`, if it can be written as a
+/// string.
+fn absolute(path: &Path) -> Option<String> {
+    path.canonicalize()
+        .ok()
+        .and_then(|abs| abs.to_str().map(String::from))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn identical_files_share_a_blob() {
+        let temp = TempDir::new().unwrap();
+        let a = temp.path().join("a.txt");
+        let b = temp.path().join("b.txt");
+        let c = temp.path().join("c.txt");
+        std::fs::write(&a, "Hello, World!").unwrap();
+        std::fs::write(&b, "Hello, World!").unwrap();
+        std::fs::write(&c, "Something else").unwrap();
+        let mut blobs = Blobs::default();
+
+        let (first, _, first_is_new) = blobs.add(&a, None);
+        let (second, _, second_is_new) = blobs.add(&b, None);
+        let (third, _, _) = blobs.add(&c, None);
+
+        assert_eq!(first, second);
+        assert!(first_is_new && !second_is_new);
+        assert_ne!(first, third);
+        assert_eq!(blobs.len(), 2);
+    }
+
+    fn included_files(blobs: &Blobs) -> Vec<PathBuf> {
+        let tokens = blobs.to_tokens().to_string();
+
+        tokens
+            .split("include_bytes !")
+            .skip(1)
+            .filter_map(|rest| rest.split('"').nth(1))
+            .map(PathBuf::from)
+            .collect()
+    }
+
+    #[test]
+    fn every_file_is_tracked() {
+        let temp = TempDir::new().unwrap();
+        let a = temp.path().join("a.txt");
+        let b = temp.path().join("b.txt");
+        std::fs::write(&a, "Hello, World!").unwrap();
+        std::fs::write(&b, "Hello, World!").unwrap();
+        let mut blobs = Blobs::default();
+
+        blobs.add(&a, None);
+        blobs.add(&b, None);
+
+        let included = included_files(&blobs);
+        assert!(included.contains(&a.canonicalize().unwrap()));
+        assert!(included.contains(&b.canonicalize().unwrap()));
+    }
+
+    #[test]
+    fn track_files_without_embedding_them() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("hashed.txt");
+        std::fs::write(&path, "Hello, World!").unwrap();
+        let mut blobs = Blobs::default();
+
+        blobs.track(&path);
+
+        assert_eq!(blobs.len(), 0);
+        assert_eq!(included_files(&blobs), [path.canonicalize().unwrap()]);
+    }
+
+    #[test]
+    #[cfg(feature = "gzip")]
+    fn compressed_files_are_tracked() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("big.txt");
+        std::fs::write(&path, "a".repeat(6000)).unwrap();
+        let mut blobs = Blobs::default();
+
+        let (_, compression, _) = blobs.add(&path, Some(Compression::Gzip));
+
+        assert_eq!(compression, Some(Compression::Gzip));
+        assert_eq!(included_files(&blobs), [path.canonicalize().unwrap()]);
+    }
+}
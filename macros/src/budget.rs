@@ -0,0 +1,161 @@
+//! Compile-time limits on how much data gets embedded.
+
+use std::fmt::Write;
+
+/// The `max_file_size` and `max_total_size` options, plus the sizes of every
+/// file seen so far.
+#[derive(Debug, Default, Clone)]
+pub(crate) struct Budget {
+    pub(crate) max_file_size: Option<u64>,
+    pub(crate) max_total_size: Option<u64>,
+    /// Files which were bigger than `max_file_size`.
+    too_big: Vec<(String, u64)>,
+    /// Files counted towards `max_total_size`.
+    sizes: Vec<(String, u64)>,
+    total: u64,
+}
+
+impl Budget {
+    /// Check a file against `max_file_size` before reading it, remembering
+    /// it for [`Budget::check()`] if it is too big.
+    pub(crate) fn allows(&mut self, path: &str, size: u64) -> bool {
+        match self.max_file_size {
+            Some(max) if size > max => {
+                self.too_big.push((path.to_string(), size));
+                false
+            }
+            _ => true,
+        }
+    }
+
+    /// Count a file towards `max_total_size`, returning `false` once the
+    /// total is too big.
+    ///
+    /// Files with the same contents are only embedded once, so they should
+    /// only be recorded once.
+    pub(crate) fn record(&mut self, path: &str, size: u64) -> bool {
+        self.sizes.push((path.to_string(), size));
+        self.total += size;
+
+        self.max_total_size.map_or(true, |max| self.total <= max)
+    }
+
+    /// Check the recorded files against the limits, returning a message
+    /// explaining which files are at fault if any limit was exceeded.
+    pub(crate) fn check(&self) -> Result<(), String> {
+        let mut msg = String::new();
+
+        if let Some(max) = self.max_file_size {
+            if !self.too_big.is_empty() {
+                writeln!(
+                    msg,
+                    "The following files are bigger than max_file_size ({}):",
+                    format_size(max)
+                )
+                .unwrap();
+                for (path, size) in &self.too_big {
+                    writeln!(msg, "  {} ({})", path, format_size(*size)).unwrap();
+                }
+            }
+        }
+
+        if let Some(max) = self.max_total_size {
+            let total = self.total;
+
+            if total > max {
+                let mut largest: Vec<_> = self.sizes.iter().collect();
+                largest.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+
+                writeln!(
+                    msg,
+                    "The embedded files add up to {}, which is more than max_total_size ({}). The largest files are:",
+                    format_size(total),
+                    format_size(max)
+                )
+                .unwrap();
+                for (path, size) in largest.into_iter().take(10) {
+                    writeln!(msg, "  {} ({})", path, format_size(*size)).unwrap();
+                }
+            }
+        }
+
+        if msg.is_empty() {
+            Ok(())
+        } else {
+            Err(msg.trim_end().to_string())
+        }
+    }
+}
+
+fn format_size(bytes: u64) -> String {
+    const UNITS: &[&str] = &["KiB", "MiB", "GiB", "TiB"];
+
+    if bytes < 1024 {
+        return format!("{} bytes", bytes);
+    }
+
+    let mut size = bytes as f64;
+    let mut unit = "bytes";
+
+    for u in UNITS {
+        if size < 1024.0 {
+            break;
+        }
+        size /= 1024.0;
+        unit = u;
+    }
+
+    format!("{:.1} {}", size, unit)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn no_limits_by_default() {
+        let mut budget = Budget::default();
+
+        assert!(budget.allows("huge.psd", u64::MAX / 2));
+        assert!(budget.record("huge.psd", u64::MAX / 2));
+        assert!(budget.check().is_ok());
+    }
+
+    #[test]
+    fn list_files_which_are_too_big() {
+        let mut budget = Budget {
+            max_file_size: Some(1000),
+            ..Default::default()
+        };
+        assert!(budget.allows("small.txt", 1000));
+        assert!(!budget.allows("drafts/huge.psd", 5 * 1024 * 1024));
+
+        let msg = budget.check().unwrap_err();
+
+        assert!(msg.contains("drafts/huge.psd (5.0 MiB)"), "{}", msg);
+        assert!(!msg.contains("small.txt"), "{}", msg);
+    }
+
+    #[test]
+    fn total_size_lists_the_largest_files() {
+        let mut budget = Budget {
+            max_total_size: Some(2048),
+            ..Default::default()
+        };
+        assert!(budget.record("a.txt", 1024));
+        assert!(!budget.record("b.txt", 1536));
+
+        let msg = budget.check().unwrap_err();
+
+        assert!(msg.contains("add up to 2.5 KiB"), "{}", msg);
+        assert!(msg.find("b.txt").unwrap() < msg.find("a.txt").unwrap());
+    }
+
+    #[test]
+    fn human_readable_sizes() {
+        assert_eq!(format_size(0), "0 bytes");
+        assert_eq!(format_size(1023), "1023 bytes");
+        assert_eq!(format_size(1024), "1.0 KiB");
+        assert_eq!(format_size(730 * 1024 * 1024), "730.0 MiB");
+    }
+}
@@ -0,0 +1,92 @@
+//! Support for looking up entries without caring about case.
+
+use crate::tree::{self, DirNode, Node};
+use std::cmp::Ordering;
+
+/// Compare two names as if they were both lower case.
+///
+/// This must match the comparison `include_dir` does at runtime.
+pub(crate) fn cmp_ignore_case(a: &str, b: &str) -> Ordering {
+    a.chars()
+        .flat_map(char::to_lowercase)
+        .cmp(b.chars().flat_map(char::to_lowercase))
+}
+
+/// The positions of a directory's children when they are sorted without
+/// caring about case, so they can be binary searched at runtime.
+pub(crate) fn index(dir: &DirNode) -> Vec<usize> {
+    let names: Vec<&str> = dir.children.keys().map(String::as_str).collect();
+    let mut index: Vec<usize> = (0..names.len()).collect();
+    // A stable sort keeps names that only differ by case in their original
+    // order, so the same one is found at runtime no matter how it's looked up
+    index.sort_by(|&a, &b| cmp_ignore_case(names[a], names[b]));
+
+    index
+}
+
+/// Find every pair of paths which only differ by case.
+pub(crate) fn conflicts(dir: &DirNode) -> Vec<(String, String)> {
+    let mut conflicts = Vec::new();
+    find_conflicts("", dir, &mut conflicts);
+    conflicts
+}
+
+fn find_conflicts(path: &str, dir: &DirNode, conflicts: &mut Vec<(String, String)>) {
+    let names: Vec<&String> = dir.children.keys().collect();
+    let index = index(dir);
+
+    for pair in index.windows(2) {
+        let (first, second) = (names[pair[0]], names[pair[1]]);
+
+        if cmp_ignore_case(first, second) == Ordering::Equal {
+            conflicts.push((tree::join(path, first), tree::join(path, second)));
+        }
+    }
+
+    for (name, child) in &dir.children {
+        if let Node::Dir(d) = child {
+            find_conflicts(&tree::join(path, name), d, conflicts);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tree::tests::{dir, file};
+
+    #[test]
+    fn sort_names_without_caring_about_case() {
+        let tree = dir(vec![
+            ("Zebra.txt", file("root", "Zebra.txt")),
+            ("apple.txt", file("root", "apple.txt")),
+            ("banana.txt", file("root", "banana.txt")),
+        ]);
+
+        assert_eq!(index(&tree), [1, 2, 0]);
+    }
+
+    #[test]
+    fn find_paths_which_only_differ_by_case() {
+        let tree = dir(vec![
+            ("Logo.PNG", file("root", "Logo.PNG")),
+            ("logo.png", file("root", "logo.png")),
+            (
+                "img",
+                Node::Dir(dir(vec![
+                    ("A.txt", file("root", "img/A.txt")),
+                    ("a.txt", file("root", "img/a.txt")),
+                    ("b.txt", file("root", "img/b.txt")),
+                ])),
+            ),
+        ]);
+
+        assert_eq!(
+            conflicts(&tree),
+            [
+                ("Logo.PNG".to_string(), "logo.png".to_string()),
+                ("img/A.txt".to_string(), "img/a.txt".to_string()),
+            ]
+        );
+    }
+}
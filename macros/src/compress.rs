@@ -0,0 +1,127 @@
+//! Compressing file contents before they get embedded.
+
+use proc_macro2::TokenStream;
+use quote::quote;
+
+/// The `compression` option.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) enum Compression {
+    Gzip,
+    Zstd,
+    Brotli,
+}
+
+impl Compression {
+    pub(crate) fn parse(name: &str) -> Compression {
+        let (compression, enabled) = match name {
+            "gzip" => (Compression::Gzip, cfg!(feature = "gzip")),
+            "zstd" => (Compression::Zstd, cfg!(feature = "zstd")),
+            "brotli" => (Compression::Brotli, cfg!(feature = "brotli")),
+            other => panic!(
+                "Expected \"compression\" to be \"gzip\", \"zstd\" or \"brotli\", found \"{}\"",
+                other
+            ),
+        };
+
+        if !enabled {
+            panic!(
+                "Using {} compression requires the \"{}\" feature on include_dir",
+                name, name
+            );
+        }
+
+        compression
+    }
+
+    /// The corresponding `include_dir::Compression` variant.
+    pub(crate) fn to_tokens(self) -> TokenStream {
+        match self {
+            Compression::Gzip => quote!(include_dir::Compression::Gzip),
+            Compression::Zstd => quote!(include_dir::Compression::Zstd),
+            Compression::Brotli => quote!(include_dir::Compression::Brotli),
+        }
+    }
+
+    pub(crate) fn compress(self, data: &[u8]) -> Vec<u8> {
+        #[allow(unused_imports)]
+        use std::io::Write;
+
+        match self {
+            #[cfg(feature = "gzip")]
+            Compression::Gzip => {
+                let mut encoder =
+                    flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::best());
+                encoder.write_all(data).unwrap();
+                encoder.finish().unwrap()
+            }
+            #[cfg(feature = "zstd")]
+            Compression::Zstd => zstd_crate::stream::encode_all(data, 19).unwrap(),
+            #[cfg(feature = "brotli")]
+            Compression::Brotli => {
+                let mut compressed = Vec::new();
+                {
+                    let mut encoder =
+                        brotli_crate::CompressorWriter::new(&mut compressed, 4096, 9, 22);
+                    encoder.write_all(data).unwrap();
+                }
+                compressed
+            }
+            #[allow(unreachable_patterns)]
+            _ => {
+                let _ = data;
+                unreachable!("Checked when parsing the \"compression\" option")
+            }
+        }
+    }
+}
+
+#[cfg(all(test, any(feature = "gzip", feature = "zstd", feature = "brotli")))]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(feature = "gzip")]
+    fn gzip_round_trip() {
+        use std::io::Read;
+
+        let data = "Hello, World! ".repeat(100);
+
+        let compressed = Compression::Gzip.compress(data.as_bytes());
+
+        assert!(compressed.len() < data.len());
+        let mut decompressed = String::new();
+        flate2::read::GzDecoder::new(compressed.as_slice())
+            .read_to_string(&mut decompressed)
+            .unwrap();
+        assert_eq!(decompressed, data);
+    }
+
+    #[test]
+    #[cfg(feature = "zstd")]
+    fn zstd_round_trip() {
+        let data = "Hello, World! ".repeat(100);
+
+        let compressed = Compression::Zstd.compress(data.as_bytes());
+
+        assert!(compressed.len() < data.len());
+        let decompressed = zstd_crate::stream::decode_all(compressed.as_slice()).unwrap();
+        assert_eq!(decompressed, data.as_bytes());
+    }
+
+    #[test]
+    #[cfg(feature = "brotli")]
+    fn brotli_round_trip() {
+        use std::io::Read;
+
+        let data = "Hello, World! ".repeat(100);
+
+        let compressed = Compression::Brotli.compress(data.as_bytes());
+
+        assert!(compressed.len() < data.len());
+        let mut decompressed = String::new();
+        brotli_crate::Decompressor::new(compressed.as_slice(), 4096)
+            .read_to_string(&mut decompressed)
+            .unwrap();
+        assert_eq!(decompressed, data);
+    }
+}
@@ -0,0 +1,294 @@
+//! Deciding which files and directories get embedded.
+
+use glob::{MatchOptions, Pattern};
+use std::{collections::HashSet, path::Path};
+
+/// How glob patterns are matched against normalized paths.
+pub(crate) const MATCH_OPTIONS: MatchOptions = MatchOptions {
+    case_sensitive: true,
+    require_literal_separator: true,
+    require_literal_leading_dot: false,
+};
+
+/// What to do when a symlink is encountered.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) enum Symlinks {
+    /// Embed whatever the symlink points to.
+    Follow,
+    /// Leave the symlink out.
+    Skip,
+    /// Fail the build.
+    Error,
+    /// Embed the symlink itself, as a `DirEntry::Symlink`.
+    Preserve,
+}
+
+impl Default for Symlinks {
+    fn default() -> Self {
+        Symlinks::Follow
+    }
+}
+
+/// What to do with anything that isn't a file, directory, or symlink we can
+/// handle (e.g. FIFOs, sockets, devices, and broken symlinks).
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) enum SpecialFiles {
+    /// Fail the build.
+    Error,
+    /// Leave it out.
+    Skip,
+}
+
+impl Default for SpecialFiles {
+    fn default() -> Self {
+        SpecialFiles::Error
+    }
+}
+
+/// The options which control which files get embedded (`include`, `exclude`,
+/// `max_depth`, `gitignore`, `skip_hidden`, `symlinks` and `special_files`).
+#[derive(Debug, Default, Clone)]
+pub(crate) struct Filter {
+    /// If non-empty, only files matching at least one of these patterns are
+    /// embedded.
+    pub(crate) include: Vec<Pattern>,
+    /// Files and directories matching any of these patterns are skipped.
+    pub(crate) exclude: Vec<Pattern>,
+    /// The deepest level to descend to, where the root's immediate children
+    /// are at depth 1. Directories at the limit are embedded without their
+    /// contents.
+    pub(crate) max_depth: Option<usize>,
+    /// Should files ignored by `.gitignore`, `.ignore` and the global git
+    /// excludes be skipped?
+    pub(crate) gitignore: bool,
+    /// Should files and directories starting with a `.` be skipped?
+    pub(crate) skip_hidden: bool,
+    /// The (normalized) paths which aren't ignored, populated by
+    /// [`Filter::load_ignore_files()`] when `gitignore` is set.
+    pub(crate) not_ignored: Option<HashSet<String>>,
+    pub(crate) symlinks: Symlinks,
+    pub(crate) special_files: SpecialFiles,
+}
+
+impl Filter {
+    /// Walk the directory tree using the same rules as `git` to figure out
+    /// which paths are ignored.
+    #[cfg(feature = "gitignore")]
+    pub(crate) fn load_ignore_files(&mut self, root: &Path) {
+        if !self.gitignore {
+            return;
+        }
+
+        let walk = ignore::WalkBuilder::new(root)
+            .standard_filters(false)
+            .ignore(true)
+            .git_ignore(true)
+            .git_global(true)
+            .git_exclude(true)
+            .parents(true)
+            .require_git(false)
+            .follow_links(self.symlinks == Symlinks::Follow)
+            .filter_entry(|entry| entry.file_name() != ".git")
+            .build();
+
+        let mut not_ignored = HashSet::new();
+
+        // Errors (e.g. symlink loops) are reported when we read the tree
+        // properly, so it's fine to skip them here
+        for entry in walk.filter_map(Result::ok) {
+            not_ignored.insert(crate::normalize_path(root, entry.path()));
+        }
+
+        self.not_ignored = Some(not_ignored);
+    }
+
+    /// The `gitignore` option is rejected when parsing the arguments if the
+    /// feature isn't enabled, so there is nothing to load.
+    #[cfg(not(feature = "gitignore"))]
+    pub(crate) fn load_ignore_files(&mut self, _root: &Path) {}
+
+    /// Would every file and directory be embedded?
+    pub(crate) fn allows_everything(&self) -> bool {
+        self.include.is_empty()
+            && self.exclude.is_empty()
+            && self.max_depth.is_none()
+            && !self.gitignore
+            && !self.skip_hidden
+            && self.symlinks == Symlinks::Follow
+    }
+
+    /// Should the directory at this (normalized) path be embedded?
+    pub(crate) fn allows_dir(&self, path: &str) -> bool {
+        self.is_visible(path) && !self.is_excluded(path)
+    }
+
+    /// Should the file at this (normalized) path be embedded?
+    pub(crate) fn allows_file(&self, path: &str) -> bool {
+        self.is_visible(path) && !self.is_excluded(path) && self.is_included(path)
+    }
+
+    /// Should the children of a directory at this depth be left out?
+    pub(crate) fn max_depth_reached(&self, depth: usize) -> bool {
+        self.max_depth.map_or(false, |max| depth >= max)
+    }
+
+    fn is_visible(&self, path: &str) -> bool {
+        if self.skip_hidden {
+            let name = path.rsplit('/').next().unwrap_or(path);
+            if name.starts_with('.') {
+                return false;
+            }
+        }
+
+        match &self.not_ignored {
+            Some(not_ignored) => not_ignored.contains(path),
+            None => true,
+        }
+    }
+
+    fn is_excluded(&self, path: &str) -> bool {
+        self.exclude
+            .iter()
+            .any(|p| p.matches_with(path, MATCH_OPTIONS))
+    }
+
+    fn is_included(&self, path: &str) -> bool {
+        self.include.is_empty()
+            || self
+                .include
+                .iter()
+                .any(|p| p.matches_with(path, MATCH_OPTIONS))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn patterns(globs: &[&str]) -> Vec<Pattern> {
+        globs.iter().map(|g| Pattern::new(g).unwrap()).collect()
+    }
+
+    #[test]
+    fn everything_is_allowed_by_default() {
+        let filter = Filter::default();
+
+        assert!(filter.allows_file("a/b/c/d.txt"));
+        assert!(filter.allows_dir("a/b/c"));
+    }
+
+    #[test]
+    fn any_filter_means_not_everything_is_allowed() {
+        assert!(Filter::default().allows_everything());
+        assert!(Filter {
+            special_files: SpecialFiles::Skip,
+            ..Default::default()
+        }
+        .allows_everything());
+
+        let filters = [
+            Filter {
+                exclude: patterns(&["*.psd"]),
+                ..Default::default()
+            },
+            Filter {
+                max_depth: Some(1),
+                ..Default::default()
+            },
+            Filter {
+                skip_hidden: true,
+                ..Default::default()
+            },
+            Filter {
+                symlinks: Symlinks::Skip,
+                ..Default::default()
+            },
+        ];
+
+        for filter in filters {
+            assert!(!filter.allows_everything(), "{:?}", filter);
+        }
+    }
+
+    #[test]
+    fn include_only_applies_to_files() {
+        let filter = Filter {
+            include: patterns(&["**/*.png", "**/*.json"]),
+            ..Default::default()
+        };
+
+        assert!(filter.allows_file("logo.png"));
+        assert!(filter.allows_file("images/icons/logo.png"));
+        assert!(filter.allows_file("data.json"));
+        assert!(!filter.allows_file("main.rs"));
+        assert!(filter.allows_dir("images"));
+    }
+
+    #[test]
+    fn exclude_takes_priority_over_include() {
+        let filter = Filter {
+            include: patterns(&["**/*.png"]),
+            exclude: patterns(&["**/*.psd", "drafts/**", "drafts"]),
+            ..Default::default()
+        };
+
+        assert!(!filter.allows_file("work.psd"));
+        assert!(!filter.allows_file("drafts/logo.png"));
+        assert!(!filter.allows_dir("drafts"));
+        assert!(filter.allows_file("final/logo.png"));
+    }
+
+    #[test]
+    fn wildcards_dont_cross_directories() {
+        let filter = Filter {
+            exclude: patterns(&["*.bak"]),
+            ..Default::default()
+        };
+
+        assert!(!filter.allows_file("index.bak"));
+        assert!(filter.allows_file("nested/index.bak"));
+    }
+
+    #[test]
+    fn skip_hidden_files() {
+        let filter = Filter {
+            skip_hidden: true,
+            ..Default::default()
+        };
+
+        assert!(!filter.allows_file(".DS_Store"));
+        assert!(!filter.allows_dir(".git"));
+        assert!(!filter.allows_file("nested/.env"));
+        assert!(filter.allows_file("nested/file.txt"));
+    }
+
+    #[test]
+    fn skip_ignored_files() {
+        let filter = Filter {
+            not_ignored: Some(
+                vec!["src".to_string(), "src/lib.rs".to_string()]
+                    .into_iter()
+                    .collect(),
+            ),
+            ..Default::default()
+        };
+
+        assert!(filter.allows_dir("src"));
+        assert!(filter.allows_file("src/lib.rs"));
+        assert!(!filter.allows_dir("target"));
+        assert!(!filter.allows_file("src/lib.rs.bak"));
+    }
+
+    #[test]
+    fn limit_the_depth() {
+        let filter = Filter {
+            max_depth: Some(2),
+            ..Default::default()
+        };
+
+        assert!(!filter.max_depth_reached(0));
+        assert!(!filter.max_depth_reached(1));
+        assert!(filter.max_depth_reached(2));
+        assert!(!Filter::default().max_depth_reached(100));
+    }
+}
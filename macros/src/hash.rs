@@ -0,0 +1,131 @@
+//! Hashing file contents and directory trees at compile time.
+
+use proc_macro2::{Literal, TokenStream};
+use quote::quote;
+
+/// The `hash` option.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) enum HashAlgorithm {
+    Sha256,
+    Blake3,
+}
+
+impl HashAlgorithm {
+    pub(crate) fn parse(name: &str) -> HashAlgorithm {
+        let (algorithm, enabled) = match name {
+            "sha256" => (HashAlgorithm::Sha256, cfg!(feature = "sha256")),
+            "blake3" => (HashAlgorithm::Blake3, cfg!(feature = "blake3")),
+            other => panic!(
+                "Expected \"hash\" to be \"sha256\" or \"blake3\", found \"{}\"",
+                other
+            ),
+        };
+
+        if !enabled {
+            panic!(
+                "Using {} hashes requires the \"{}\" feature on include_dir",
+                name, name
+            );
+        }
+
+        algorithm
+    }
+
+    pub(crate) fn hash(self, data: &[u8]) -> [u8; 32] {
+        match self {
+            #[cfg(feature = "sha256")]
+            HashAlgorithm::Sha256 => {
+                use sha2::Digest;
+                sha2::Sha256::digest(data).into()
+            }
+            #[cfg(feature = "blake3")]
+            HashAlgorithm::Blake3 => blake3::hash(data).into(),
+            #[allow(unreachable_patterns)]
+            _ => {
+                let _ = data;
+                unreachable!("Checked when parsing the \"hash\" option")
+            }
+        }
+    }
+
+    /// Combine the hashes of a directory's children into a single digest.
+    ///
+    /// Each child contributes a tag saying what kind of entry it is, its name
+    /// and its own hash, so renaming or moving a file changes the digest.
+    pub(crate) fn hash_dir<'a>(
+        self,
+        children: impl IntoIterator<Item = (EntryKind, &'a str, [u8; 32])>,
+    ) -> [u8; 32] {
+        let mut buffer = Vec::new();
+
+        for (kind, name, hash) in children {
+            buffer.push(kind as u8);
+            buffer.extend_from_slice(&(name.len() as u64).to_le_bytes());
+            buffer.extend_from_slice(name.as_bytes());
+            buffer.extend_from_slice(&hash);
+        }
+
+        self.hash(&buffer)
+    }
+
+    /// An `include_dir::Hash` expression.
+    pub(crate) fn to_tokens(self, hash: [u8; 32]) -> TokenStream {
+        let algorithm = match self {
+            HashAlgorithm::Sha256 => quote!(include_dir::HashAlgorithm::Sha256),
+            HashAlgorithm::Blake3 => quote!(include_dir::HashAlgorithm::Blake3),
+        };
+        let bytes = hash.iter().map(|b| Literal::u8_suffixed(*b));
+
+        quote!(include_dir::Hash::new(#algorithm, [#(#bytes),*]))
+    }
+}
+
+/// The kinds of entry that go into a directory's digest.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) enum EntryKind {
+    File = b'f' as isize,
+    Dir = b'd' as isize,
+    Symlink = b'l' as isize,
+}
+
+#[cfg(all(test, any(feature = "sha256", feature = "blake3")))]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(feature = "sha256")]
+    fn sha256_of_a_known_value() {
+        let hash = HashAlgorithm::Sha256.hash(b"abc");
+
+        assert_eq!(
+            hash[..4],
+            [0xba, 0x78, 0x16, 0xbf],
+            "the first few bytes of the test vector from FIPS 180-2"
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "blake3")]
+    fn blake3_matches_the_reference_implementation() {
+        let hash = HashAlgorithm::Blake3.hash(b"abc");
+
+        assert_eq!(hash, *blake3::hash(b"abc").as_bytes());
+    }
+
+    #[test]
+    fn renaming_a_child_changes_the_digest() {
+        let algorithm = if cfg!(feature = "sha256") {
+            HashAlgorithm::Sha256
+        } else {
+            HashAlgorithm::Blake3
+        };
+        let contents = algorithm.hash(b"Hello, World!");
+
+        let original = algorithm.hash_dir(vec![(EntryKind::File, "a.txt", contents)]);
+        let renamed = algorithm.hash_dir(vec![(EntryKind::File, "b.txt", contents)]);
+        let as_dir = algorithm.hash_dir(vec![(EntryKind::Dir, "a.txt", contents)]);
+
+        assert_ne!(original, renamed);
+        assert_ne!(original, as_dir);
+    }
+}
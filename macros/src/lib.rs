@@ -3,8 +3,27 @@
 //! You probably don't want to use this crate directly.
 #![cfg_attr(feature = "nightly", feature(track_path, proc_macro_tracked_env))]
 
-use proc_macro::{TokenStream, TokenTree};
-use proc_macro2::Literal;
+mod args;
+mod blobs;
+mod budget;
+mod case;
+mod compress;
+mod filter;
+mod hash;
+#[cfg(feature = "mime")]
+mod mime;
+mod mode;
+mod rewrite;
+mod tree;
+
+use crate::{
+    args::Args,
+    blobs::Blobs,
+    hash::EntryKind,
+    mode::MODE_VARIABLE,
+    tree::{read_tree, DirNode, FileNode, Node, OnConflict},
+};
+use proc_macro::TokenStream;
 use quote::quote;
 use std::{
     error::Error,
@@ -16,108 +35,296 @@ use std::{
 /// Embed the contents of a directory in your crate.
 #[proc_macro]
 pub fn include_dir(input: TokenStream) -> TokenStream {
-    let tokens: Vec<_> = input.into_iter().collect();
+    let args = Args::parse(input);
+
+    if args.name.is_some() && args.paths.len() != 1 {
+        panic!("`name` can only be used when embedding a single directory");
+    }
+
+    let mut tree = DirNode::default();
+    let mut conflicts = Vec::new();
+
+    for path in &args.paths {
+        let root = resolve_path(path, get_env).unwrap();
+        tree.merge(read_tree(&root, &args.filter), &mut conflicts);
+    }
+
+    let tree = args.rewrites.apply(tree, &mut conflicts);
+
+    if args.on_conflict == OnConflict::Error && !conflicts.is_empty() {
+        panic!(
+            "The following paths refer to more than one entry: {}",
+            conflicts.join(", ")
+        );
+    }
 
-    let path = match tokens.as_slice() {
-        [TokenTree::Literal(lit)] => unwrap_string_literal(lit),
-        _ => panic!("This macro only accepts a single, non-empty string argument"),
+    let warnings: Vec<_> = case::conflicts(&tree)
+        .into_iter()
+        .map(|(first, second)| {
+            warning(&format!(
+                "\"{}\" and \"{}\" only differ by case, so `get_entry_ignore_case()` will only find the first one",
+                first, second
+            ))
+        })
+        .collect();
+
+    let embed = args
+        .mode
+        .should_embed(get_env, cfg!(feature = "embed"), cfg!(debug_assertions));
+    let live_root = live_root(&args, embed);
+    let mut ctx = Context {
+        args,
+        blobs: Blobs::default(),
+        embed,
+        live_root,
     };
+    let (tokens, _) = expand_dir("", &tree, &mut ctx);
+
+    if let Err(msg) = ctx.args.budget.check() {
+        panic!("{}", msg);
+    }
 
-    let path = resolve_path(&path, get_env).unwrap();
+    // Each unique blob is declared once, so identical files share storage
+    let blobs = ctx.blobs.to_tokens();
+
+    // Using option_env!() tells cargo to recompile when the variable changes
+    let tokens = quote! {
+        {
+            const _: Option<&str> = option_env!(#MODE_VARIABLE);
+            #(#warnings)*
+            #blobs
+            #tokens
+        }
+    };
 
-    expand_dir(&path, &path).into()
+    tokens.into()
 }
 
-fn unwrap_string_literal(lit: &proc_macro::Literal) -> String {
-    let mut repr = lit.to_string();
-    if !repr.starts_with('"') || !repr.ends_with('"') {
-        panic!("This macro only accepts a single, non-empty string argument")
+/// Generate code which makes the compiler emit a warning, because proc
+/// macros can't do that themselves on stable.
+fn warning(message: &str) -> proc_macro2::TokenStream {
+    quote! {
+        const _: () = {
+            #[deprecated(note = #message)]
+            struct Warning;
+            let _ = Warning;
+        };
     }
+}
 
-    repr.remove(0);
-    repr.pop();
-
-    repr
+/// Everything needed while generating code for the embedded tree.
+struct Context {
+    args: Args,
+    blobs: Blobs,
+    /// Should file contents be embedded, or read from disk at runtime?
+    embed: bool,
+    /// Where directories should be listed from at runtime, if `live = true`.
+    live_root: Option<String>,
 }
 
-fn expand_dir(root: &Path, path: &Path) -> proc_macro2::TokenStream {
-    let children = read_dir(path).unwrap_or_else(|e| {
-        panic!(
-            "Unable to read the entries in \"{}\": {}",
-            path.display(),
-            e
-        )
-    });
+/// Check that the `live` option can be used, returning the directory which
+/// will be listed at runtime.
+fn live_root(args: &Args, embed: bool) -> Option<String> {
+    if !args.live || embed {
+        return None;
+    }
 
-    let mut child_tokens = Vec::new();
+    if args.paths.len() != 1 {
+        panic!("`live = true` can only be used when embedding a single directory");
+    }
+    if !args.rewrites.is_empty() {
+        panic!("`live = true` can't be combined with `flatten`, `rename_extensions` or `mount`");
+    }
+    // Directories are listed from disk at runtime without any filtering, so
+    // they would contain things a release build leaves out
+    if !args.filter.allows_everything() {
+        panic!("`live = true` can't be combined with `include`, `exclude`, `max_depth`, `gitignore`, `skip_hidden` or `symlinks`");
+    }
 
-    for child in children {
-        if child.is_dir() {
-            let tokens = expand_dir(root, &child);
-            child_tokens.push(quote! {
-                include_dir::DirEntry::Dir(#tokens)
-            });
-        } else if child.is_file() {
-            let tokens = expand_file(root, &child);
-            child_tokens.push(quote! {
-                include_dir::DirEntry::File(#tokens)
-            });
-        } else {
-            panic!("\"{}\" is neither a file nor a directory", child.display());
+    let root = resolve_path(&args.paths[0], get_env).unwrap();
+    let root = root
+        .to_str()
+        .unwrap_or_else(|| panic!("\"{}\" is not valid UTF-8", root.display()));
+
+    Some(root.to_string())
+}
+
+/// Generate the code for a directory, plus its digest if the `hash` option
+/// was used.
+fn expand_dir(
+    path: &str,
+    dir: &DirNode,
+    ctx: &mut Context,
+) -> (proc_macro2::TokenStream, Option<[u8; 32]>) {
+    let mut child_tokens = Vec::new();
+    let mut child_hashes = Vec::new();
+
+    for (name, child) in &dir.children {
+        let child_path = tree::join(path, name);
+
+        let (tokens, kind, hash) = match child {
+            Node::Dir(d) => {
+                let (tokens, hash) = expand_dir(&child_path, d, ctx);
+                let tokens = quote! {
+                    include_dir::DirEntry::Dir(#tokens)
+                };
+                (tokens, EntryKind::Dir, hash)
+            }
+            Node::File(f) => {
+                let (tokens, hash) = expand_file(&child_path, f, ctx);
+                let tokens = quote! {
+                    include_dir::DirEntry::File(#tokens)
+                };
+                (tokens, EntryKind::File, hash)
+            }
+            Node::Symlink(link) => {
+                let target = link.target.to_string_lossy();
+                let hash = ctx.args.hash.map(|h| h.hash(target.as_bytes()));
+                let tokens = quote! {
+                    include_dir::DirEntry::Symlink(include_dir::Symlink::new(#child_path, #target))
+                };
+                (tokens, EntryKind::Symlink, hash)
+            }
+        };
+        child_tokens.push(tokens);
+        if let Some(hash) = hash {
+            child_hashes.push((kind, name.as_str(), hash));
         }
     }
 
-    let path = normalize_path(root, path);
-
-    quote! {
+    let tokens = quote! {
         include_dir::Dir::new(#path, &[ #(#child_tokens),* ])
+    };
+
+    // Live directories are listed at runtime, so there is nothing to index
+    let tokens = match &ctx.live_root {
+        Some(root) => quote!(#tokens.with_root(#root)),
+        None => {
+            let index = case::index(dir);
+            quote!(#tokens.with_sorted().with_case_index(&[ #(#index),* ]))
+        }
+    };
+
+    let tokens = match &ctx.args.name {
+        Some(name) => quote!(#tokens.with_root_name(#name)),
+        None => tokens,
+    };
+
+    match ctx.args.hash {
+        Some(algorithm) => {
+            let digest = algorithm.hash_dir(child_hashes);
+            let digest_tokens = algorithm.to_tokens(digest);
+            (quote!(#tokens.with_digest(#digest_tokens)), Some(digest))
+        }
+        None => (tokens, None),
     }
 }
 
-fn expand_file(root: &Path, path: &Path) -> proc_macro2::TokenStream {
+/// Count a file towards `max_total_size`, failing as soon as the limit is
+/// exceeded instead of reading everything else first.
+fn record_size(ctx: &mut Context, path: &str, size: u64) {
+    if !ctx.args.budget.record(path, size) {
+        if let Err(msg) = ctx.args.budget.check() {
+            panic!("{}", msg);
+        }
+    }
+}
+
+fn expand_file(
+    normalized_path: &str,
+    file: &FileNode,
+    ctx: &mut Context,
+) -> (proc_macro2::TokenStream, Option<[u8; 32]>) {
+    let FileNode { root, path } = file;
+
     let abs = path
         .canonicalize()
         .unwrap_or_else(|e| panic!("failed to resolve \"{}\": {}", path.display(), e));
-    let literal = match abs.to_str() {
-        Some(abs) => {
-            #[cfg(not(debug_assertions))]
-            {quote!(include_bytes!(#abs))}
-            #[cfg(debug_assertions)]
-            {quote!(&[])}
-        },
-        None => {
-            #[cfg(not(debug_assertions))]
-            {
-            let contents = read_file(path);
-            let literal = Literal::byte_string(&contents);
-            quote!(#literal)
-            }
-            #[cfg(debug_assertions)]
-            {quote!(&[])}
+    let size = abs
+        .metadata()
+        .unwrap_or_else(|e| panic!("Unable to read \"{}\": {}", path.display(), e))
+        .len();
+
+    // Don't bother reading files which are too big, the build is going to
+    // fail anyway
+    if !ctx.args.budget.allows(normalized_path, size) {
+        return (quote!(include_dir::File::new(#normalized_path, &[])), None);
+    }
+
+    let (literal, compression) = if !ctx.embed {
+        record_size(ctx, normalized_path, size);
+        (quote!(&[]), None)
+    } else {
+        let (name, compression, is_new) = ctx.blobs.add(&abs, ctx.args.compression);
+        if is_new {
+            record_size(ctx, normalized_path, size);
         }
+        (quote!(&#name), compression)
     };
 
-    let normalized_path = normalize_path(root, path);
+    let tokens = if !ctx.embed {
+        let root_str = root
+            .to_str()
+            .unwrap_or_else(|| panic!("\"{}\" is not valid UTF-8", root.display()));
+        let tokens = quote! {
+            include_dir::File::new(#normalized_path, #literal).with_root(#root_str)
+        };
+
+        let tokens = match &ctx.args.name {
+            Some(name) => quote!(#tokens.with_root_name(#name)),
+            None => tokens,
+        };
+
+        // The file was renamed or moved, so remember where it came from
+        let source = normalize_path(root, path);
+        if source == normalized_path {
+            tokens
+        } else {
+            quote!(#tokens.with_source(#source))
+        }
+    } else {
+        quote! {
+            include_dir::File::new(#normalized_path, #literal)
+        }
+    };
 
-    #[cfg(not(debug_assertions))]
-    let tokens = quote! {
-        include_dir::File::new(#normalized_path, #literal)
+    let tokens = match compression {
+        Some(c) => {
+            let c = c.to_tokens();
+            quote!(#tokens.with_compression(#c))
+        }
+        None => tokens,
     };
 
-    #[cfg(debug_assertions)]
-    let tokens = if root.to_str().is_some() {
-        let r = root.to_str().unwrap();
-            quote! {
-            include_dir::File::new(#normalized_path, #literal, #r)
+    // Hashing and sniffing the MIME type read the file, so the results
+    // would go stale if the compiler didn't know to rebuild when it changes
+    if (ctx.args.hash.is_some() || cfg!(feature = "mime")) && !ctx.embed {
+        ctx.blobs.track(&abs);
+    }
+
+    let hash = ctx
+        .args
+        .hash
+        .map(|algorithm| (algorithm, algorithm.hash(&read_file(path))));
+    let tokens = match hash {
+        Some((algorithm, hash)) => {
+            let hash = algorithm.to_tokens(hash);
+            quote!(#tokens.with_hash(#hash))
         }
-    } else {
-        panic!("root.to_str().is_none()")
+        None => tokens,
     };
 
-    match metadata(path) {
+    let tokens = match metadata(path) {
         Some(metadata) => quote!(#tokens.with_metadata(#metadata)),
         None => tokens,
-    }
+    };
+
+    let tokens = match mime_type(normalized_path, path) {
+        Some(mime_type) => quote!(#tokens.with_mime_type(#mime_type)),
+        None => tokens,
+    };
+
+    (tokens, hash.map(|(_, hash)| hash))
 }
 
 fn metadata(path: &Path) -> Option<proc_macro2::TokenStream> {
@@ -143,6 +350,20 @@ fn metadata(path: &Path) -> Option<proc_macro2::TokenStream> {
     })
 }
 
+/// The arguments for `File::with_mime_type()`, using the path the file will
+/// be embedded at so renamed extensions are taken into account.
+#[cfg(feature = "mime")]
+fn mime_type(normalized_path: &str, path: &Path) -> Option<proc_macro2::TokenStream> {
+    let (mime_type, is_text) = mime::classify(normalized_path, &read_file(path));
+
+    Some(quote!(#mime_type, #is_text))
+}
+
+#[cfg(not(feature = "mime"))]
+fn mime_type(_normalized_path: &str, _path: &Path) -> Option<proc_macro2::TokenStream> {
+    None
+}
+
 /// Make sure that paths use the same separator regardless of whether the host
 /// machine is Windows or Linux.
 fn normalize_path(root: &Path, path: &Path) -> String {
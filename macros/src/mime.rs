@@ -0,0 +1,82 @@
+//! Working out what kind of data a file contains.
+
+/// Magic numbers for common binary formats, used when a file's extension
+/// doesn't tell us anything.
+const SIGNATURES: &[(&[u8], &str)] = &[
+    (b"\x89PNG\r\n\x1a\n", "image/png"),
+    (b"\xff\xd8\xff", "image/jpeg"),
+    (b"GIF87a", "image/gif"),
+    (b"GIF89a", "image/gif"),
+    (b"%PDF-", "application/pdf"),
+    (b"PK\x03\x04", "application/zip"),
+    (b"\x1f\x8b", "application/gzip"),
+    (b"\0asm", "application/wasm"),
+    (b"wOFF", "font/woff"),
+    (b"wOF2", "font/woff2"),
+];
+
+/// Guess a file's MIME type from its name, falling back to sniffing its
+/// contents, and check whether it contains text.
+pub(crate) fn classify(name: &str, contents: &[u8]) -> (String, bool) {
+    let is_text = is_text(contents);
+
+    let mime_type = mime_guess::from_path(name)
+        .first_raw()
+        .or_else(|| sniff(contents))
+        .unwrap_or(if is_text {
+            "text/plain"
+        } else {
+            "application/octet-stream"
+        });
+
+    (mime_type.to_string(), is_text)
+}
+
+fn sniff(contents: &[u8]) -> Option<&'static str> {
+    if contents.starts_with(b"RIFF") && contents.get(8..12) == Some(b"WEBP") {
+        return Some("image/webp");
+    }
+
+    SIGNATURES
+        .iter()
+        .find(|(magic, _)| contents.starts_with(magic))
+        .map(|(_, mime_type)| *mime_type)
+}
+
+/// Text is anything that is valid UTF-8 and doesn't contain null bytes.
+fn is_text(contents: &[u8]) -> bool {
+    !contents.contains(&0) && std::str::from_utf8(contents).is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn use_the_extension_when_possible() {
+        assert_eq!(
+            classify("index.html", b"<h1>Hello</h1>"),
+            ("text/html".to_string(), true)
+        );
+        assert_eq!(
+            classify("logo.png", b"not really a png"),
+            ("image/png".to_string(), true)
+        );
+    }
+
+    #[test]
+    fn sniff_files_without_a_known_extension() {
+        assert_eq!(
+            classify("logo", b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR"),
+            ("image/png".to_string(), false)
+        );
+        assert_eq!(
+            classify("LICENSE", b"MIT License"),
+            ("text/plain".to_string(), true)
+        );
+        assert_eq!(
+            classify("data.unknown", b"\0\x01\x02"),
+            ("application/octet-stream".to_string(), false)
+        );
+    }
+}
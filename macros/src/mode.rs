@@ -0,0 +1,90 @@
+//! Deciding whether files should be embedded or read from disk at runtime.
+
+/// The `mode` option.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) enum Mode {
+    /// Embed the file contents in the binary.
+    Embed,
+    /// Read files from their original location at runtime.
+    Disk,
+    /// Read from disk in debug builds and embed in release builds.
+    Auto,
+}
+
+impl Default for Mode {
+    fn default() -> Self {
+        Mode::Auto
+    }
+}
+
+/// The environment variable which overrides the `mode` option.
+pub(crate) const MODE_VARIABLE: &str = "INCLUDE_DIR_MODE";
+
+impl Mode {
+    pub(crate) fn parse(key: &str, name: &str) -> Mode {
+        match name {
+            "embed" => Mode::Embed,
+            "disk" => Mode::Disk,
+            "auto" => Mode::Auto,
+            other => panic!(
+                "Expected \"{}\" to be \"embed\", \"disk\" or \"auto\", found \"{}\"",
+                key, other
+            ),
+        }
+    }
+
+    /// Work out whether files should be embedded.
+    ///
+    /// The `embed` feature always wins so release binaries are guaranteed to
+    /// be self-contained, followed by the `INCLUDE_DIR_MODE` environment
+    /// variable and then the `mode` option.
+    pub(crate) fn should_embed(
+        self,
+        get_env: impl Fn(&str) -> Option<String>,
+        embed_feature: bool,
+        debug_assertions: bool,
+    ) -> bool {
+        if embed_feature {
+            return true;
+        }
+
+        let mode = match get_env(MODE_VARIABLE) {
+            Some(value) => Mode::parse(MODE_VARIABLE, &value),
+            None => self,
+        };
+
+        match mode {
+            Mode::Embed => true,
+            Mode::Disk => false,
+            Mode::Auto => !debug_assertions,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn auto_depends_on_the_profile() {
+        assert!(!Mode::Auto.should_embed(|_| None, false, true));
+        assert!(Mode::Auto.should_embed(|_| None, false, false));
+    }
+
+    #[test]
+    fn the_environment_variable_overrides_the_option() {
+        let env = |name: &str| {
+            assert_eq!(name, MODE_VARIABLE);
+            Some("disk".to_string())
+        };
+
+        assert!(!Mode::Embed.should_embed(env, false, false));
+    }
+
+    #[test]
+    fn the_embed_feature_always_wins() {
+        let env = |_: &str| Some("disk".to_string());
+
+        assert!(Mode::Disk.should_embed(env, true, true));
+    }
+}
@@ -0,0 +1,213 @@
+//! Changing the layout of a directory tree before it gets embedded.
+
+use crate::{
+    filter::MATCH_OPTIONS,
+    tree::{join, DirNode, Node},
+};
+use glob::Pattern;
+
+/// The `flatten`, `rename_extensions` and `mount` options.
+#[derive(Debug, Default, Clone)]
+pub(crate) struct Rewrites {
+    /// Directories whose contents should be moved into their parent.
+    pub(crate) flatten: Vec<Pattern>,
+    /// Pairs of `(from, to)` extensions, where an empty `to` removes the
+    /// extension altogether.
+    pub(crate) rename_extensions: Vec<(String, String)>,
+    /// A virtual directory the whole tree should be placed under.
+    pub(crate) mount: Option<String>,
+}
+
+impl Rewrites {
+    /// Would applying these rewrites leave the tree untouched?
+    pub(crate) fn is_empty(&self) -> bool {
+        self.flatten.is_empty() && self.rename_extensions.is_empty() && self.mount.is_none()
+    }
+
+    /// Apply each rewrite rule to the tree, in the order they are documented.
+    ///
+    /// Rewriting may cause two entries to end up with the same path, in which
+    /// case the path is added to `conflicts` and the entry which sorted later
+    /// wins.
+    pub(crate) fn apply(&self, tree: DirNode, conflicts: &mut Vec<String>) -> DirNode {
+        let tree = self.rewrite_dir("", "", tree, conflicts);
+
+        match &self.mount {
+            Some(mount) => mount
+                .split('/')
+                .filter(|component| !component.is_empty())
+                .rev()
+                .fold(tree, |tree, component| {
+                    let mut parent = DirNode::default();
+                    parent
+                        .children
+                        .insert(component.to_string(), Node::Dir(tree));
+                    parent
+                }),
+            None => tree,
+        }
+    }
+
+    /// Rewrite the children of a directory, where `original` is the
+    /// directory's path on disk (used when matching `flatten` patterns) and
+    /// `path` is where it will be embedded.
+    fn rewrite_dir(
+        &self,
+        original: &str,
+        path: &str,
+        dir: DirNode,
+        conflicts: &mut Vec<String>,
+    ) -> DirNode {
+        let mut rewritten = DirNode::default();
+
+        for (name, child) in dir.children {
+            let child_original = join(original, &name);
+
+            match child {
+                Node::Dir(d) if self.is_flattened(&child_original) => {
+                    let d = self.rewrite_dir(&child_original, path, d, conflicts);
+                    for (name, grandchild) in d.children {
+                        rewritten.insert(path, name, grandchild, conflicts);
+                    }
+                }
+                Node::Dir(d) => {
+                    let d = self.rewrite_dir(&child_original, &join(path, &name), d, conflicts);
+                    rewritten.insert(path, name, Node::Dir(d), conflicts);
+                }
+                Node::File(f) => {
+                    let name = self.rename(name);
+                    rewritten.insert(path, name, Node::File(f), conflicts);
+                }
+                Node::Symlink(link) => {
+                    rewritten.insert(path, name, Node::Symlink(link), conflicts);
+                }
+            }
+        }
+
+        rewritten
+    }
+
+    fn is_flattened(&self, path: &str) -> bool {
+        self.flatten
+            .iter()
+            .any(|p| p.matches_with(path, MATCH_OPTIONS))
+    }
+
+    /// Apply the first matching `rename_extensions` rule to a file name.
+    fn rename(&self, name: String) -> String {
+        for (from, to) in &self.rename_extensions {
+            let suffix = format!(".{}", from);
+
+            if name.len() > suffix.len() && name.ends_with(&suffix) {
+                let stem = &name[..name.len() - suffix.len()];
+
+                return if to.is_empty() {
+                    stem.to_string()
+                } else {
+                    format!("{}.{}", stem, to)
+                };
+            }
+        }
+
+        name
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tree::tests::{dir, file};
+
+    fn rename_rules(rules: &[(&str, &str)]) -> Rewrites {
+        Rewrites {
+            rename_extensions: rules
+                .iter()
+                .map(|(from, to)| (from.to_string(), to.to_string()))
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn rename_extensions() {
+        let rewrites = rename_rules(&[("min.js", "js"), ("html", "")]);
+
+        assert_eq!(rewrites.rename("app.min.js".to_string()), "app.js");
+        assert_eq!(rewrites.rename("about.html".to_string()), "about");
+        assert_eq!(rewrites.rename("style.css".to_string()), "style.css");
+        assert_eq!(rewrites.rename(".html".to_string()), ".html");
+    }
+
+    #[test]
+    fn renaming_onto_an_existing_file_is_a_conflict() {
+        let rewrites = rename_rules(&[("min.js", "js")]);
+        let tree = dir(vec![
+            ("app.js", file("root", "app.js")),
+            ("app.min.js", file("root", "app.min.js")),
+        ]);
+        let mut conflicts = Vec::new();
+
+        let got = rewrites.apply(tree, &mut conflicts);
+
+        assert_eq!(conflicts, ["app.js"]);
+        assert_eq!(got, dir(vec![("app.js", file("root", "app.min.js"))]));
+    }
+
+    #[test]
+    fn flatten_directories() {
+        let rewrites = Rewrites {
+            flatten: vec![Pattern::new("dist").unwrap()],
+            ..Default::default()
+        };
+        let tree = dir(vec![
+            (
+                "dist",
+                Node::Dir(dir(vec![
+                    ("index.html", file("root", "dist/index.html")),
+                    (
+                        "css",
+                        Node::Dir(dir(vec![("app.css", file("root", "dist/css/app.css"))])),
+                    ),
+                ])),
+            ),
+            ("README.md", file("root", "README.md")),
+        ]);
+        let mut conflicts = Vec::new();
+
+        let got = rewrites.apply(tree, &mut conflicts);
+
+        assert!(conflicts.is_empty());
+        assert_eq!(
+            got,
+            dir(vec![
+                ("README.md", file("root", "README.md")),
+                (
+                    "css",
+                    Node::Dir(dir(vec![("app.css", file("root", "dist/css/app.css"))]))
+                ),
+                ("index.html", file("root", "dist/index.html")),
+            ])
+        );
+    }
+
+    #[test]
+    fn mount_under_a_prefix() {
+        let rewrites = Rewrites {
+            mount: Some("/static/assets/".to_string()),
+            ..Default::default()
+        };
+        let tree = dir(vec![("logo.png", file("root", "logo.png"))]);
+        let mut conflicts = Vec::new();
+
+        let got = rewrites.apply(tree, &mut conflicts);
+
+        let expected = dir(vec![(
+            "static",
+            Node::Dir(dir(vec![(
+                "assets",
+                Node::Dir(dir(vec![("logo.png", file("root", "logo.png"))])),
+            )])),
+        )]);
+        assert_eq!(got, expected);
+    }
+}
@@ -0,0 +1,403 @@
+//! Reading directory trees from disk and merging them together.
+
+use crate::filter::{Filter, SpecialFiles, Symlinks};
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+/// What to do when more than one root contains the same path.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) enum OnConflict {
+    /// Entries from later roots replace entries from earlier ones.
+    Override,
+    /// Fail the build, listing every conflicting path.
+    Error,
+}
+
+impl Default for OnConflict {
+    fn default() -> Self {
+        OnConflict::Override
+    }
+}
+
+/// An entry in a directory tree which is about to be embedded.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Node {
+    Dir(DirNode),
+    File(FileNode),
+    Symlink(SymlinkNode),
+}
+
+/// A directory, with its children sorted by name.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub(crate) struct DirNode {
+    pub(crate) children: BTreeMap<String, Node>,
+}
+
+/// A file and where it can be found on disk.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct FileNode {
+    /// The root directory this file was found under.
+    pub(crate) root: PathBuf,
+    /// The file's location on disk.
+    pub(crate) path: PathBuf,
+}
+
+/// A symlink which should be embedded as-is.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct SymlinkNode {
+    /// Where the symlink points.
+    pub(crate) target: PathBuf,
+}
+
+/// Read the directory tree under `root`, skipping anything the [`Filter`]
+/// doesn't allow.
+pub(crate) fn read_tree(root: &Path, filter: &Filter) -> DirNode {
+    let mut filter = filter.clone();
+    filter.load_ignore_files(root);
+
+    let mut ancestors = Vec::new();
+    read_dir_node(root, root, &filter, 0, &mut ancestors).unwrap_or_default()
+}
+
+/// Read a directory, returning `None` if it had entries but all of them were
+/// filtered out.
+///
+/// The `ancestors` are the canonical paths of every directory we are
+/// currently inside, and are used to detect symlink cycles.
+fn read_dir_node(
+    root: &Path,
+    path: &Path,
+    filter: &Filter,
+    depth: usize,
+    ancestors: &mut Vec<PathBuf>,
+) -> Option<DirNode> {
+    let canonical = path
+        .canonicalize()
+        .unwrap_or_else(|e| panic!("failed to resolve \"{}\": {}", path.display(), e));
+    if ancestors.contains(&canonical) {
+        panic!(
+            "Following the symlinks at \"{}\" leads to a cycle",
+            path.display()
+        );
+    }
+
+    let children = if filter.max_depth_reached(depth) {
+        Vec::new()
+    } else {
+        crate::read_dir(path).unwrap_or_else(|e| {
+            panic!(
+                "Unable to read the entries in \"{}\": {}",
+                path.display(),
+                e
+            )
+        })
+    };
+
+    ancestors.push(canonical);
+    let mut dir = DirNode::default();
+
+    for child in &children {
+        let normalized = crate::normalize_path(root, child);
+        let name = child
+            .file_name()
+            .expect("Directory entries always have a name")
+            .to_string_lossy()
+            .into_owned();
+
+        // Filters come first, so excluded entries never trigger the symlink
+        // or special file policies
+        let allowed = if child.is_dir() {
+            filter.allows_dir(&normalized)
+        } else {
+            filter.allows_file(&normalized)
+        };
+        if !allowed {
+            continue;
+        }
+
+        let is_symlink = child
+            .symlink_metadata()
+            .map(|m| m.file_type().is_symlink())
+            .unwrap_or(false);
+
+        if is_symlink {
+            match filter.symlinks {
+                Symlinks::Follow => {}
+                Symlinks::Skip => continue,
+                Symlinks::Error => panic!("\"{}\" is a symlink", child.display()),
+                Symlinks::Preserve => {
+                    if filter.allows_file(&normalized) {
+                        let target = std::fs::read_link(child).unwrap_or_else(|e| {
+                            panic!("Unable to read the symlink \"{}\": {}", child.display(), e)
+                        });
+                        dir.children
+                            .insert(name, Node::Symlink(SymlinkNode { target }));
+                    }
+                    continue;
+                }
+            }
+        }
+
+        if child.is_dir() {
+            if let Some(node) = read_dir_node(root, child, filter, depth + 1, ancestors) {
+                dir.children.insert(name, Node::Dir(node));
+            }
+        } else if child.is_file() {
+            let node = FileNode {
+                root: root.to_path_buf(),
+                path: child.clone(),
+            };
+            dir.children.insert(name, Node::File(node));
+        } else if filter.special_files == SpecialFiles::Error {
+            panic!(
+                "\"{}\" is neither a file nor a directory (use `special_files = \"skip\"` to ignore it)",
+                child.display()
+            );
+        }
+    }
+
+    ancestors.pop();
+
+    if dir.children.is_empty() && !children.is_empty() {
+        return None;
+    }
+
+    Some(dir)
+}
+
+impl DirNode {
+    /// Merge `other` into this directory, with entries from `other` taking
+    /// precedence.
+    ///
+    /// Directories which exist in both trees are merged recursively, while
+    /// anything else that exists in both is a conflict. Each conflict's path
+    /// (relative to the root) is added to `conflicts`.
+    pub(crate) fn merge(&mut self, other: DirNode, conflicts: &mut Vec<String>) {
+        self.merge_at("", other, conflicts);
+    }
+
+    /// Add a child to this directory (which is at `path`), merging it with
+    /// any existing entry that has the same name.
+    pub(crate) fn insert(
+        &mut self,
+        path: &str,
+        name: String,
+        node: Node,
+        conflicts: &mut Vec<String>,
+    ) {
+        match (self.children.get_mut(&name), node) {
+            (Some(Node::Dir(ours)), Node::Dir(theirs)) => {
+                ours.merge_at(&join(path, &name), theirs, conflicts);
+            }
+            (Some(ours), theirs) => {
+                conflicts.push(join(path, &name));
+                *ours = theirs;
+            }
+            (None, theirs) => {
+                self.children.insert(name, theirs);
+            }
+        }
+    }
+
+    fn merge_at(&mut self, path: &str, other: DirNode, conflicts: &mut Vec<String>) {
+        for (name, theirs) in other.children {
+            self.insert(path, name, theirs, conflicts);
+        }
+    }
+}
+
+/// Get the (normalized) path for an entry inside the directory at `path`.
+pub(crate) fn join(path: &str, name: &str) -> String {
+    if path.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}/{}", path, name)
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    pub(crate) fn file(root: &str, path: &str) -> Node {
+        Node::File(FileNode {
+            root: PathBuf::from(root),
+            path: Path::new(root).join(path),
+        })
+    }
+
+    pub(crate) fn dir(children: Vec<(&str, Node)>) -> DirNode {
+        DirNode {
+            children: children
+                .into_iter()
+                .map(|(name, node)| (name.to_string(), node))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn later_trees_take_precedence() {
+        let mut defaults = dir(vec![
+            ("logo.png", file("defaults", "logo.png")),
+            ("style.css", file("defaults", "style.css")),
+        ]);
+        let product = dir(vec![("logo.png", file("product", "logo.png"))]);
+        let mut conflicts = Vec::new();
+
+        defaults.merge(product, &mut conflicts);
+
+        assert_eq!(conflicts, ["logo.png"]);
+        assert_eq!(
+            defaults,
+            dir(vec![
+                ("logo.png", file("product", "logo.png")),
+                ("style.css", file("defaults", "style.css")),
+            ])
+        );
+    }
+
+    #[test]
+    fn directories_are_merged_recursively() {
+        let mut defaults = dir(vec![(
+            "img",
+            Node::Dir(dir(vec![("a.png", file("defaults", "img/a.png"))])),
+        )]);
+        let product = dir(vec![(
+            "img",
+            Node::Dir(dir(vec![("b.png", file("product", "img/b.png"))])),
+        )]);
+        let mut conflicts = Vec::new();
+
+        defaults.merge(product, &mut conflicts);
+
+        assert!(conflicts.is_empty());
+        assert_eq!(
+            defaults,
+            dir(vec![(
+                "img",
+                Node::Dir(dir(vec![
+                    ("a.png", file("defaults", "img/a.png")),
+                    ("b.png", file("product", "img/b.png")),
+                ]))
+            )])
+        );
+    }
+
+    #[test]
+    fn a_file_replacing_a_directory_is_a_conflict() {
+        let mut defaults = dir(vec![("docs", Node::Dir(DirNode::default()))]);
+        let product = dir(vec![("docs", file("product", "docs"))]);
+        let mut conflicts = Vec::new();
+
+        defaults.merge(product, &mut conflicts);
+
+        assert_eq!(conflicts, ["docs"]);
+        assert_eq!(defaults, dir(vec![("docs", file("product", "docs"))]));
+    }
+
+    #[cfg(unix)]
+    mod symlinks {
+        use super::*;
+        use glob::Pattern;
+        use std::os::unix::fs::symlink;
+        use tempfile::TempDir;
+
+        fn filter(symlinks: Symlinks, special_files: SpecialFiles) -> Filter {
+            Filter {
+                symlinks,
+                special_files,
+                ..Default::default()
+            }
+        }
+
+        #[test]
+        fn preserve_symlinks() {
+            let temp = TempDir::new().unwrap();
+            std::fs::write(temp.path().join("file.txt"), "").unwrap();
+            symlink("file.txt", temp.path().join("link.txt")).unwrap();
+
+            let tree = read_tree(
+                temp.path(),
+                &filter(Symlinks::Preserve, SpecialFiles::Error),
+            );
+
+            assert_eq!(
+                tree.children["link.txt"],
+                Node::Symlink(SymlinkNode {
+                    target: PathBuf::from("file.txt")
+                })
+            );
+        }
+
+        #[test]
+        fn skip_broken_symlinks() {
+            let temp = TempDir::new().unwrap();
+            std::fs::write(temp.path().join("file.txt"), "").unwrap();
+            symlink("missing.txt", temp.path().join("broken.txt")).unwrap();
+
+            let tree = read_tree(temp.path(), &filter(Symlinks::Follow, SpecialFiles::Skip));
+
+            assert_eq!(tree.children.keys().collect::<Vec<_>>(), ["file.txt"]);
+        }
+
+        #[test]
+        #[should_panic(expected = "neither a file nor a directory")]
+        fn broken_symlinks_are_an_error_by_default() {
+            let temp = TempDir::new().unwrap();
+            symlink("missing.txt", temp.path().join("broken.txt")).unwrap();
+
+            read_tree(temp.path(), &Filter::default());
+        }
+
+        #[test]
+        fn excluded_entries_dont_trigger_the_policies() {
+            let temp = TempDir::new().unwrap();
+            std::fs::write(temp.path().join("file.txt"), "").unwrap();
+            symlink("file.txt", temp.path().join("link.txt")).unwrap();
+            let _socket =
+                std::os::unix::net::UnixListener::bind(temp.path().join("app.sock")).unwrap();
+            let filter = Filter {
+                exclude: vec![
+                    Pattern::new("*.sock").unwrap(),
+                    Pattern::new("link.txt").unwrap(),
+                ],
+                ..filter(Symlinks::Error, SpecialFiles::Error)
+            };
+
+            let tree = read_tree(temp.path(), &filter);
+
+            assert_eq!(tree.children.keys().collect::<Vec<_>>(), ["file.txt"]);
+        }
+
+        #[test]
+        #[should_panic(expected = "leads to a cycle")]
+        fn detect_symlink_cycles() {
+            let temp = TempDir::new().unwrap();
+            std::fs::create_dir(temp.path().join("nested")).unwrap();
+            symlink("..", temp.path().join("nested").join("parent")).unwrap();
+
+            read_tree(temp.path(), &Filter::default());
+        }
+
+        #[test]
+        fn skip_symlinks() {
+            let temp = TempDir::new().unwrap();
+            std::fs::create_dir(temp.path().join("nested")).unwrap();
+            std::fs::write(temp.path().join("nested").join("file.txt"), "").unwrap();
+            symlink("..", temp.path().join("nested").join("parent")).unwrap();
+
+            let tree = read_tree(temp.path(), &filter(Symlinks::Skip, SpecialFiles::Error));
+
+            let nested = tree.children["nested"].clone();
+            assert_eq!(
+                nested,
+                Node::Dir(dir(vec![(
+                    "file.txt",
+                    file(&temp.path().to_string_lossy(), "nested/file.txt")
+                )]))
+            );
+        }
+    }
+}